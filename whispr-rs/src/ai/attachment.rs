@@ -0,0 +1,168 @@
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose, Engine as _};
+use clap::ValueEnum;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::imageops::{self, FilterType};
+use image::{ImageEncoder, RgbaImage};
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UploadFormat {
+    /// Lossless, largest
+    Png,
+    Jpeg,
+    /// Smallest; needs the `webp` cargo feature
+    Webp,
+}
+
+/// How screenshots are shrunk and encoded before upload.
+#[derive(Clone, Copy, Debug)]
+pub struct ImageEncoding {
+    pub format: UploadFormat,
+    /// Longest side in pixels; larger screenshots are scaled down.
+    pub max_dimension: Option<u32>,
+    /// 1-100, ignored for PNG.
+    pub quality: u8,
+}
+
+/// A screenshot ready to attach to a request.
+#[derive(Clone, Debug)]
+pub struct EncodedImage {
+    pub mime_type: &'static str,
+    pub base64: String,
+    pub width: u32,
+    pub height: u32,
+    /// Size of the encoded image before base64.
+    pub bytes: usize,
+}
+
+pub fn encode_screenshot(screenshot: &RgbaImage, encoding: &ImageEncoding) -> Result<EncodedImage> {
+    let resized;
+    let image = match encoding.max_dimension {
+        Some(max) if screenshot.width().max(screenshot.height()) > max => {
+            let scale = max as f32 / screenshot.width().max(screenshot.height()) as f32;
+            let width = ((screenshot.width() as f32 * scale).round() as u32).max(1);
+            let height = ((screenshot.height() as f32 * scale).round() as u32).max(1);
+            resized = imageops::resize(screenshot, width, height, FilterType::Triangle);
+            &resized
+        }
+        _ => screenshot,
+    };
+
+    let (width, height) = image.dimensions();
+    let mut data = Vec::new();
+    let mime_type = match encoding.format {
+        UploadFormat::Png => {
+            PngEncoder::new(&mut data)
+                .write_image(image.as_raw(), width, height, image::ColorType::Rgba8)
+                .context("failed to encode screenshot as PNG")?;
+            "image/png"
+        }
+        UploadFormat::Jpeg => {
+            // JPEG has no alpha channel.
+            let rgb = image::DynamicImage::ImageRgba8(image.clone()).to_rgb8();
+            JpegEncoder::new_with_quality(&mut data, encoding.quality.clamp(1, 100))
+                .write_image(rgb.as_raw(), width, height, image::ColorType::Rgb8)
+                .context("failed to encode screenshot as JPEG")?;
+            "image/jpeg"
+        }
+        UploadFormat::Webp => {
+            data = encode_webp(image, encoding.quality)?;
+            "image/webp"
+        }
+    };
+
+    Ok(EncodedImage {
+        mime_type,
+        base64: general_purpose::STANDARD.encode(&data),
+        width,
+        height,
+        bytes: data.len(),
+    })
+}
+
+#[cfg(feature = "webp")]
+fn encode_webp(image: &RgbaImage, quality: u8) -> Result<Vec<u8>> {
+    let encoded = webp::Encoder::from_rgba(image.as_raw(), image.width(), image.height())
+        .encode(quality.clamp(1, 100) as f32);
+    Ok(encoded.to_vec())
+}
+
+#[cfg(not(feature = "webp"))]
+fn encode_webp(_image: &RgbaImage, _quality: u8) -> Result<Vec<u8>> {
+    Err(anyhow::anyhow!(
+        "WebP upload needs a build with the `webp` feature (cargo build --features webp)"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    /// A gradient, so JPEG quality makes a visible difference in size.
+    fn screenshot(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, y| {
+            Rgba([(x * 7 % 256) as u8, (y * 13 % 256) as u8, ((x ^ y) % 256) as u8, 255])
+        })
+    }
+
+    fn encode(image: &RgbaImage, format: UploadFormat, max_dimension: Option<u32>, quality: u8) -> Result<EncodedImage> {
+        encode_screenshot(image, &ImageEncoding { format, max_dimension, quality })
+    }
+
+    fn decoded(image: &EncodedImage) -> Vec<u8> {
+        general_purpose::STANDARD.decode(&image.base64).unwrap()
+    }
+
+    #[test]
+    fn downscales_the_longest_side_keeping_the_aspect_ratio() {
+        let wide = encode(&screenshot(400, 300), UploadFormat::Png, Some(100), 80).unwrap();
+        assert_eq!((wide.width, wide.height), (100, 75));
+        let tall = encode(&screenshot(90, 300), UploadFormat::Png, Some(100), 80).unwrap();
+        assert_eq!((tall.width, tall.height), (30, 100));
+
+        let png = image::load_from_memory(&decoded(&wide)).unwrap();
+        assert_eq!((png.width(), png.height()), (100, 75));
+    }
+
+    #[test]
+    fn small_screenshots_keep_their_size() {
+        for max_dimension in [None, Some(400), Some(1000)] {
+            let image = encode(&screenshot(400, 300), UploadFormat::Png, max_dimension, 80).unwrap();
+            assert_eq!((image.width, image.height), (400, 300));
+        }
+        // Extreme aspect ratios never round down to zero.
+        let line = encode(&screenshot(1000, 1), UploadFormat::Png, Some(100), 80).unwrap();
+        assert_eq!((line.width, line.height), (100, 1));
+    }
+
+    #[test]
+    fn png_and_jpeg_mime_types() {
+        let png = encode(&screenshot(64, 48), UploadFormat::Png, None, 80).unwrap();
+        assert_eq!(png.mime_type, "image/png");
+        assert!(decoded(&png).starts_with(b"\x89PNG"));
+        assert_eq!(png.bytes, decoded(&png).len());
+
+        let jpeg = encode(&screenshot(64, 48), UploadFormat::Jpeg, None, 80).unwrap();
+        assert_eq!(jpeg.mime_type, "image/jpeg");
+        assert!(decoded(&jpeg).starts_with(&[0xff, 0xd8]));
+    }
+
+    #[test]
+    fn jpeg_quality_changes_the_size() {
+        let image = screenshot(256, 256);
+        let low = encode(&image, UploadFormat::Jpeg, None, 10).unwrap();
+        let high = encode(&image, UploadFormat::Jpeg, None, 95).unwrap();
+        assert!(low.bytes < high.bytes, "{} >= {}", low.bytes, high.bytes);
+        // Out-of-range quality is clamped rather than rejected.
+        assert!(encode(&image, UploadFormat::Jpeg, None, 0).is_ok());
+    }
+
+    #[cfg(not(feature = "webp"))]
+    #[test]
+    fn webp_needs_the_feature() {
+        let error = encode(&screenshot(8, 8), UploadFormat::Webp, None, 80).unwrap_err();
+        assert!(error.to_string().contains("--features webp"));
+    }
+}
@@ -0,0 +1,415 @@
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader};
+use std::time::Duration;
+
+use super::{GenerationConfig, ResponseBackend, ResponseRequest, Role, Turn};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiRequest {
+    contents: Vec<GeminiContent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    generation_config: Option<GeminiGenerationConfig>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    safety_settings: Vec<SafetySetting>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiGenerationConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_output_tokens: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct SafetySetting {
+    category: &'static str,
+    threshold: SafetyThreshold,
+}
+
+#[derive(Serialize)]
+struct GeminiContent {
+    role: &'static str,
+    parts: Vec<GeminiPart>,
+}
+
+impl GeminiContent {
+    fn new(turn: &Turn) -> Self {
+        let mut parts = vec![GeminiPart::Text { text: turn.text.clone() }];
+        if let Some(text) = turn.ocr_part() {
+            parts.push(GeminiPart::Text { text });
+        }
+        if let Some(image) = &turn.image {
+            parts.push(GeminiPart::InlineData {
+                inline_data: InlineData {
+                    mime_type: image.mime_type.to_string(),
+                    data: image.base64.clone(),
+                },
+            });
+        }
+        Self {
+            role: match turn.role {
+                Role::User => "user",
+                Role::Model => "model",
+            },
+            parts,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum GeminiPart {
+    Text { text: String },
+    InlineData { inline_data: InlineData },
+}
+
+#[derive(Serialize)]
+struct InlineData {
+    mime_type: String,
+    data: String,
+}
+
+#[derive(Deserialize)]
+struct GeminiResponse {
+    // The last chunk of a stream may only carry usage metadata.
+    #[serde(default)]
+    candidates: Vec<GeminiCandidate>,
+}
+
+impl GeminiResponse {
+    fn text(&self) -> Option<String> {
+        self.candidates
+            .first()
+            .and_then(|c| c.content.parts.first())
+            .map(|p| p.text.clone())
+    }
+}
+
+#[derive(Deserialize)]
+struct GeminiCandidate {
+    // Missing when the candidate was blocked by a safety filter.
+    #[serde(default)]
+    content: GeminiContentResponse,
+}
+
+#[derive(Deserialize, Default)]
+struct GeminiContentResponse {
+    parts: Vec<GeminiPartResponse>,
+}
+
+#[derive(Deserialize)]
+struct GeminiPartResponse {
+    #[serde(default)]
+    text: String,
+}
+
+/// Harm categories Gemini accepts safety settings for.
+const HARM_CATEGORIES: [&str; 4] = [
+    "HARM_CATEGORY_HARASSMENT",
+    "HARM_CATEGORY_HATE_SPEECH",
+    "HARM_CATEGORY_SEXUALLY_EXPLICIT",
+    "HARM_CATEGORY_DANGEROUS_CONTENT",
+];
+
+/// Blocking threshold applied to every harm category.
+#[derive(ValueEnum, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SafetyThreshold {
+    /// Turns the safety filter off entirely.
+    Off,
+    BlockNone,
+    BlockOnlyHigh,
+    BlockMediumAndAbove,
+    BlockLowAndAbove,
+}
+
+pub struct GeminiConfig {
+    pub model: String,
+    /// API root, e.g. `https://generativelanguage.googleapis.com/v1beta`.
+    pub base_url: String,
+    /// `None` keeps Gemini's default safety settings.
+    pub safety_threshold: Option<SafetyThreshold>,
+}
+
+/// Google Gemini via the `generateContent` REST endpoint.
+pub struct GeminiBackend {
+    api_key: String,
+    config: GeminiConfig,
+    generation: GenerationConfig,
+}
+
+impl GeminiBackend {
+    pub fn new(api_key: String, config: GeminiConfig, generation: GenerationConfig) -> Self {
+        Self { api_key, config, generation }
+    }
+
+    fn generation_config(&self) -> Option<GeminiGenerationConfig> {
+        let GenerationConfig { temperature, top_p, max_tokens } = self.generation;
+        if temperature.is_none() && top_p.is_none() && max_tokens.is_none() {
+            return None;
+        }
+        Some(GeminiGenerationConfig {
+            temperature,
+            top_p,
+            max_output_tokens: max_tokens,
+        })
+    }
+
+    fn request_body(&self, request: &ResponseRequest) -> GeminiRequest {
+        GeminiRequest {
+            contents: request.turns().map(GeminiContent::new).collect(),
+            generation_config: self.generation_config(),
+            safety_settings: self.safety_settings(),
+        }
+    }
+
+    fn safety_settings(&self) -> Vec<SafetySetting> {
+        match self.config.safety_threshold {
+            Some(threshold) => HARM_CATEGORIES
+                .iter()
+                .map(|&category| SafetySetting { category, threshold })
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl ResponseBackend for GeminiBackend {
+    fn name(&self) -> &'static str {
+        "Gemini"
+    }
+
+    fn generate(&self, request: &ResponseRequest) -> Result<String> {
+        let resp = self.send(request, "generateContent", &[], Duration::from_secs(30))?;
+        let body: GeminiResponse = resp.json().context("failed to parse Gemini response")?;
+
+        Ok(body.text().unwrap_or_else(|| "No response from Gemini".to_string()))
+    }
+
+    fn generate_stream(&self, request: &ResponseRequest, on_token: &mut dyn FnMut(&str)) -> Result<String> {
+        // The timeout covers reading the whole body, so allow for long answers.
+        let resp = self.send(request, "streamGenerateContent", &[("alt", "sse")], Duration::from_secs(120))?;
+
+        read_stream(BufReader::new(resp), on_token)
+    }
+}
+
+impl GeminiBackend {
+    /// Posts the request to the given model method and checks the status.
+    fn send(
+        &self,
+        request: &ResponseRequest,
+        method: &str,
+        query: &[(&str, &str)],
+        timeout: Duration,
+    ) -> Result<reqwest::blocking::Response> {
+        let client = reqwest::blocking::Client::new();
+        let url = format!(
+            "{}/models/{}:{}",
+            self.config.base_url.trim_end_matches('/'),
+            self.config.model,
+            method
+        );
+
+        let resp = client
+            .post(&url)
+            .query(&[("key", self.api_key.as_str())])
+            .query(query)
+            .json(&self.request_body(request))
+            .timeout(timeout)
+            .send()
+            .context("failed to call Gemini API")?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let error_body = resp.text().unwrap_or_else(|_| "Unable to read error body".to_string());
+            return Err(anyhow!("Gemini API error: {} - {}", status, error_body));
+        }
+        Ok(resp)
+    }
+}
+
+/// Collects the text of a `streamGenerateContent?alt=sse` response, handing
+/// each chunk to `on_token`. Lines other than `data:` (blank separators,
+/// comments) are skipped, as are chunks without text such as the final
+/// usage-metadata one.
+fn read_stream(reader: impl BufRead, on_token: &mut dyn FnMut(&str)) -> Result<String> {
+    let mut full = String::new();
+    for line in reader.lines() {
+        let line = line.context("failed to read Gemini stream")?;
+        let Some(data) = line.strip_prefix("data:") else {
+            continue;
+        };
+        let chunk: GeminiResponse = serde_json::from_str(data.trim())
+            .context("failed to parse Gemini stream chunk")?;
+        if let Some(text) = chunk.text() {
+            on_token(&text);
+            full.push_str(&text);
+        }
+    }
+
+    if full.is_empty() {
+        full = "No response from Gemini".to_string();
+        on_token(&full);
+    }
+    Ok(full)
+}
+
+pub fn get_api_key_from_env_or_arg(cli_key: Option<&str>) -> Result<String> {
+    let gemini_env = std::env::var("GEMINI_API_KEY").ok();
+    let gemini_key = cli_key.or(gemini_env.as_deref());
+
+    match gemini_key {
+        Some(key) => Ok(key.to_string()),
+        None => Err(anyhow!(
+            "❌ GEMINI_API_KEY not set!\n\nGet your free API key:\n→ https://aistudio.google.com/app/apikey\n\nThen set it:\n→ setx GEMINI_API_KEY \"your_key_here\"\n\nRestart your terminal and try again."
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::EncodedImage;
+    use serde_json::{json, Value};
+
+    fn backend(generation: GenerationConfig, safety_threshold: Option<SafetyThreshold>) -> GeminiBackend {
+        let config = GeminiConfig {
+            model: "gemini-test".to_string(),
+            base_url: "http://localhost".to_string(),
+            safety_threshold,
+        };
+        GeminiBackend::new("key".to_string(), config, generation)
+    }
+
+    fn body(backend: &GeminiBackend) -> Value {
+        let current = Turn::user("What is this?".to_string(), "", None);
+        let request = ResponseRequest {
+            history: &[],
+            current: &current,
+        };
+        serde_json::to_value(backend.request_body(&request)).unwrap()
+    }
+
+    #[test]
+    fn request_leaves_out_unset_settings() {
+        let body = body(&backend(GenerationConfig::default(), None));
+        assert_eq!(
+            body,
+            json!({"contents": [{"role": "user", "parts": [{"text": "What is this?"}]}]})
+        );
+    }
+
+    #[test]
+    fn request_uses_camel_case_keys() {
+        let generation = GenerationConfig {
+            temperature: None,
+            top_p: Some(0.5),
+            max_tokens: Some(256),
+        };
+        let body = body(&backend(generation, Some(SafetyThreshold::BlockOnlyHigh)));
+        assert_eq!(body["generationConfig"], json!({"topP": 0.5, "maxOutputTokens": 256}));
+        let settings = body["safetySettings"].as_array().unwrap();
+        assert_eq!(settings.len(), HARM_CATEGORIES.len());
+        assert_eq!(
+            settings[0],
+            json!({"category": "HARM_CATEGORY_HARASSMENT", "threshold": "BLOCK_ONLY_HIGH"})
+        );
+    }
+
+    #[test]
+    fn safety_thresholds_use_api_names() {
+        let names: Vec<Value> = [
+            SafetyThreshold::Off,
+            SafetyThreshold::BlockNone,
+            SafetyThreshold::BlockOnlyHigh,
+            SafetyThreshold::BlockMediumAndAbove,
+            SafetyThreshold::BlockLowAndAbove,
+        ]
+        .iter()
+        .map(|t| serde_json::to_value(t).unwrap())
+        .collect();
+        assert_eq!(
+            names,
+            ["OFF", "BLOCK_NONE", "BLOCK_ONLY_HIGH", "BLOCK_MEDIUM_AND_ABOVE", "BLOCK_LOW_AND_ABOVE"]
+        );
+    }
+
+    #[test]
+    fn request_parts_carry_ocr_text_and_image() {
+        let image = EncodedImage {
+            mime_type: "image/jpeg",
+            base64: "AAAA".to_string(),
+            width: 1,
+            height: 1,
+            bytes: 3,
+        };
+        let current = Turn::user("Explain".to_string(), "error 42", Some(image));
+        let request = ResponseRequest {
+            history: &[],
+            current: &current,
+        };
+        let body = serde_json::to_value(backend(GenerationConfig::default(), None).request_body(&request)).unwrap();
+        let parts = &body["contents"][0]["parts"];
+        assert_eq!(parts[0], json!({"text": "Explain"}));
+        assert!(parts[1]["text"].as_str().unwrap().ends_with("\nerror 42"));
+        assert_eq!(parts[2], json!({"inline_data": {"mime_type": "image/jpeg", "data": "AAAA"}}));
+    }
+
+    fn read(stream: &str) -> Result<(String, Vec<String>)> {
+        let mut tokens = Vec::new();
+        let full = read_stream(stream.as_bytes(), &mut |token| tokens.push(token.to_string()))?;
+        Ok((full, tokens))
+    }
+
+    #[test]
+    fn stream_collects_data_lines() {
+        let stream = concat!(
+            ": keep-alive\n",
+            "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Hello\"}]}}]}\n",
+            "\n",
+            "event: message\n",
+            "data:{\"candidates\": [{\"content\": {\"parts\": [{\"text\": \" world\"}]}}]}\n",
+            "\n",
+        );
+        let (full, tokens) = read(stream).unwrap();
+        assert_eq!(full, "Hello world");
+        assert_eq!(tokens, ["Hello", " world"]);
+    }
+
+    #[test]
+    fn stream_skips_chunks_without_text() {
+        let stream = concat!(
+            "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Hi\"}]}}]}\n",
+            // Blocked by a safety filter: no content.
+            "data: {\"candidates\": [{\"finishReason\": \"SAFETY\"}]}\n",
+            "data: {\"usageMetadata\": {\"totalTokenCount\": 12}}\n",
+            "data: {\"candidates\": []}\n",
+        );
+        let (full, tokens) = read(stream).unwrap();
+        assert_eq!(full, "Hi");
+        assert_eq!(tokens, ["Hi"]);
+    }
+
+    #[test]
+    fn empty_stream_falls_back_to_a_message() {
+        for stream in ["", "\n\n", "data: {\"usageMetadata\": {}}\n"] {
+            let (full, tokens) = read(stream).unwrap();
+            assert_eq!(full, "No response from Gemini");
+            assert_eq!(tokens, [full]);
+        }
+    }
+
+    #[test]
+    fn stream_rejects_malformed_chunks() {
+        let error = read("data: {not json\n").unwrap_err();
+        assert!(error.to_string().contains("failed to parse Gemini stream chunk"));
+    }
+}
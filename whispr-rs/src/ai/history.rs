@@ -0,0 +1,204 @@
+use super::EncodedImage;
+
+/// Rough prompt cost of one attached screenshot (Gemini bills 258 tokens per image).
+const IMAGE_TOKENS: usize = 258;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    User,
+    Model,
+}
+
+#[derive(Clone)]
+pub struct Turn {
+    pub role: Role,
+    pub text: String,
+    /// Text recognised on screen, sent as its own part after `text`.
+    pub ocr_text: Option<String>,
+    /// Screenshot attached to a user turn.
+    pub image: Option<EncodedImage>,
+}
+
+impl Turn {
+    /// A user turn; empty OCR text is dropped.
+    pub fn user(text: String, ocr_text: &str, image: Option<EncodedImage>) -> Self {
+        let ocr_text = ocr_text.trim();
+        Self {
+            role: Role::User,
+            text,
+            ocr_text: (!ocr_text.is_empty()).then(|| ocr_text.to_string()),
+            image,
+        }
+    }
+
+    fn model(text: &str) -> Self {
+        Self {
+            role: Role::Model,
+            text: text.to_string(),
+            ocr_text: None,
+            image: None,
+        }
+    }
+
+    /// The OCR text with a short header so the model knows where it came from.
+    pub fn ocr_part(&self) -> Option<String> {
+        self.ocr_text
+            .as_ref()
+            .map(|text| format!("Text recognized on the user's screen (OCR, may contain errors):\n{}", text))
+    }
+
+    fn estimated_tokens(&self) -> usize {
+        // ~4 characters per token is close enough for budgeting.
+        let chars = self.text.len() + self.ocr_text.as_ref().map_or(0, String::len);
+        chars.div_ceil(4) + self.image.as_ref().map_or(0, |_| IMAGE_TOKENS)
+    }
+}
+
+/// Previous exchanges that are replayed to the model so follow-up questions
+/// ("and what about the second error?") have context.
+///
+/// Oldest exchanges are dropped once there are more than `max_exchanges` or
+/// the estimated size exceeds `token_budget`. The newest exchange is kept
+/// even when it alone is over the budget.
+#[derive(Clone)]
+pub struct Conversation {
+    turns: Vec<Turn>,
+    max_exchanges: usize,
+    token_budget: usize,
+}
+
+impl Conversation {
+    pub fn new(max_exchanges: usize, token_budget: usize) -> Self {
+        Self {
+            turns: Vec::new(),
+            max_exchanges,
+            token_budget,
+        }
+    }
+
+    pub fn turns(&self) -> &[Turn] {
+        &self.turns
+    }
+
+    pub fn exchanges(&self) -> usize {
+        self.turns.len() / 2
+    }
+
+    pub fn reset(&mut self) {
+        self.turns.clear();
+    }
+
+    /// Appends a user turn and the model's reply, then trims to the limits.
+    pub fn record(&mut self, user: Turn, reply: &str) {
+        self.turns.push(user);
+        self.turns.push(Turn::model(reply));
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.exchanges() > self.max_exchanges
+            || (self.exchanges() > 1 && self.estimated_tokens() > self.token_budget)
+        {
+            self.turns.drain(..2);
+        }
+    }
+
+    fn estimated_tokens(&self) -> usize {
+        self.turns.iter().map(Turn::estimated_tokens).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image() -> EncodedImage {
+        EncodedImage {
+            mime_type: "image/png",
+            base64: String::new(),
+            width: 1,
+            height: 1,
+            bytes: 0,
+        }
+    }
+
+    /// An exchange of `chars` characters in the question and none in the reply.
+    fn record(conversation: &mut Conversation, text: &str, chars: usize) {
+        conversation.record(Turn::user(format!("{text}{}", " ".repeat(chars - text.len())), "", None), "");
+    }
+
+    fn questions(conversation: &Conversation) -> Vec<&str> {
+        conversation
+            .turns()
+            .iter()
+            .filter(|t| t.role == Role::User)
+            .map(|t| t.text.trim())
+            .collect()
+    }
+
+    #[test]
+    fn estimates_four_characters_per_token_plus_images() {
+        let turn = Turn::user("a".repeat(10), "  ocr  ", None);
+        assert_eq!(turn.ocr_text.as_deref(), Some("ocr"));
+        assert_eq!(turn.estimated_tokens(), 4);
+        assert_eq!(Turn::user(String::new(), "", Some(image())).estimated_tokens(), IMAGE_TOKENS);
+        assert_eq!(Turn::user(String::new(), " ", None).ocr_text, None);
+    }
+
+    #[test]
+    fn drops_oldest_exchanges_over_the_budget() {
+        let mut conversation = Conversation::new(10, 100);
+        for text in ["one", "two", "three"] {
+            record(&mut conversation, text, 160);
+        }
+        assert_eq!(questions(&conversation), ["two", "three"]);
+        assert_eq!(conversation.exchanges(), 2);
+    }
+
+    #[test]
+    fn drops_oldest_exchanges_over_the_count() {
+        let mut conversation = Conversation::new(2, 10_000);
+        for text in ["one", "two", "three"] {
+            record(&mut conversation, text, 10);
+        }
+        assert_eq!(questions(&conversation), ["two", "three"]);
+    }
+
+    #[test]
+    fn keeps_the_newest_exchange_over_the_budget() {
+        let mut conversation = Conversation::new(10, 100);
+        record(&mut conversation, "small", 40);
+        conversation.record(Turn::user("huge".to_string(), "", Some(image())), "reply");
+        assert_eq!(questions(&conversation), ["huge"]);
+        assert_eq!(conversation.turns()[1].text, "reply");
+    }
+
+    #[test]
+    fn images_count_towards_the_budget() {
+        let mut conversation = Conversation::new(10, IMAGE_TOKENS + 20);
+        conversation.record(Turn::user("first".to_string(), "", Some(image())), "ok");
+        record(&mut conversation, "second", 40);
+        assert_eq!(questions(&conversation), ["first", "second"]);
+        record(&mut conversation, "third", 40);
+        assert_eq!(questions(&conversation), ["second", "third"]);
+    }
+
+    #[test]
+    fn no_history_keeps_nothing() {
+        let mut conversation = Conversation::new(0, 1000);
+        record(&mut conversation, "one", 10);
+        assert!(conversation.turns().is_empty());
+    }
+
+    #[test]
+    fn reset_clears_all_turns() {
+        let mut conversation = Conversation::new(5, 1000);
+        record(&mut conversation, "one", 10);
+        record(&mut conversation, "two", 10);
+        conversation.reset();
+        assert_eq!(conversation.exchanges(), 0);
+        assert!(conversation.turns().is_empty());
+        record(&mut conversation, "three", 10);
+        assert_eq!(questions(&conversation), ["three"]);
+    }
+}
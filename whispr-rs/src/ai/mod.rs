@@ -0,0 +1,157 @@
+use anyhow::Result;
+use clap::ValueEnum;
+
+mod attachment;
+mod gemini;
+mod history;
+mod ollama;
+mod openai;
+
+pub use attachment::{encode_screenshot, EncodedImage, ImageEncoding, UploadFormat};
+pub use gemini::{get_api_key_from_env_or_arg, GeminiBackend, GeminiConfig, SafetyThreshold};
+pub use history::{Conversation, Role, Turn};
+pub use ollama::OllamaBackend;
+pub use openai::OpenAiBackend;
+
+/// Everything a backend needs to answer one capture.
+pub struct ResponseRequest<'a> {
+    /// Earlier exchanges, oldest first, sent before the current prompt.
+    pub history: &'a [Turn],
+    /// The prompt for this capture, with OCR text and screenshot if any.
+    pub current: &'a Turn,
+}
+
+impl ResponseRequest<'_> {
+    /// History followed by the current turn.
+    pub fn turns(&self) -> impl Iterator<Item = &Turn> {
+        self.history.iter().chain(std::iter::once(self.current))
+    }
+}
+
+/// A language model that can answer a prompt about a screenshot.
+pub trait ResponseBackend: Send + Sync {
+    /// Short name used in progress and error messages.
+    fn name(&self) -> &'static str;
+
+    fn generate(&self, request: &ResponseRequest) -> Result<String>;
+
+    /// Like [`generate`](Self::generate), but hands each chunk of text to
+    /// `on_token` as soon as it arrives. Backends without streaming support
+    /// deliver the whole answer as a single chunk.
+    fn generate_stream(&self, request: &ResponseRequest, on_token: &mut dyn FnMut(&str)) -> Result<String> {
+        let text = self.generate(request)?;
+        on_token(&text);
+        Ok(text)
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackendKind {
+    /// Google Gemini (needs GEMINI_API_KEY)
+    Gemini,
+    /// Any OpenAI-compatible chat/completions endpoint
+    Openai,
+    /// Local Ollama server, works offline
+    Ollama,
+}
+
+/// Sampling parameters shared by all backends; `None` leaves the server default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GenerationConfig {
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub max_tokens: Option<u32>,
+}
+
+/// Connection settings for all backends; only the selected one is used.
+pub struct BackendOptions {
+    pub generation: GenerationConfig,
+    pub gemini_key: Option<String>,
+    pub gemini_model: String,
+    pub gemini_url: String,
+    pub gemini_safety: Option<SafetyThreshold>,
+    pub openai_key: Option<String>,
+    pub openai_url: String,
+    pub openai_model: String,
+    pub ollama_url: String,
+    pub ollama_model: String,
+}
+
+pub fn create_backend(kind: BackendKind, options: &BackendOptions) -> Result<Box<dyn ResponseBackend>> {
+    Ok(match kind {
+        BackendKind::Gemini => {
+            let api_key = get_api_key_from_env_or_arg(options.gemini_key.as_deref())?;
+            let config = GeminiConfig {
+                model: options.gemini_model.clone(),
+                base_url: options.gemini_url.clone(),
+                safety_threshold: options.gemini_safety,
+            };
+            Box::new(GeminiBackend::new(api_key, config, options.generation))
+        }
+        BackendKind::Openai => {
+            let env_key = std::env::var("OPENAI_API_KEY").ok();
+            let api_key = options.openai_key.clone().or(env_key);
+            Box::new(OpenAiBackend::new(
+                &options.openai_url,
+                &options.openai_model,
+                api_key,
+                options.generation,
+            ))
+        }
+        BackendKind::Ollama => Box::new(OllamaBackend::new(
+            &options.ollama_url,
+            &options.ollama_model,
+            options.generation,
+        )),
+    })
+}
+
+/// What was on screen for one capture. Without a screenshot only the OCR
+/// text is sent, which suits text-only models and keeps requests small.
+pub struct ScreenContext<'a> {
+    pub ocr_text: &'a str,
+    pub screenshot: Option<&'a EncodedImage>,
+}
+
+pub fn generate_response(
+    backend: &dyn ResponseBackend,
+    prompt: String,
+    screen: &ScreenContext,
+    conversation: &mut Conversation,
+) -> Result<String> {
+    respond(backend, prompt, screen, conversation, None)
+}
+
+pub fn generate_response_stream(
+    backend: &dyn ResponseBackend,
+    prompt: String,
+    screen: &ScreenContext,
+    conversation: &mut Conversation,
+    on_token: &mut dyn FnMut(&str),
+) -> Result<String> {
+    respond(backend, prompt, screen, conversation, Some(on_token))
+}
+
+/// Sends the capture together with the conversation so far and records the
+/// exchange once the backend has answered.
+fn respond(
+    backend: &dyn ResponseBackend,
+    prompt: String,
+    screen: &ScreenContext,
+    conversation: &mut Conversation,
+    on_token: Option<&mut dyn FnMut(&str)>,
+) -> Result<String> {
+    let current = Turn::user(prompt, screen.ocr_text, screen.screenshot.cloned());
+    let request = ResponseRequest {
+        history: conversation.turns(),
+        current: &current,
+    };
+
+    let reply = match on_token {
+        Some(on_token) => backend.generate_stream(&request, on_token)?,
+        None => backend.generate(&request)?,
+    };
+
+    conversation.record(current, &reply);
+    Ok(reply)
+}
@@ -0,0 +1,179 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::{GenerationConfig, ResponseBackend, ResponseRequest, Role, Turn};
+
+#[derive(Serialize)]
+struct OllamaChatRequest {
+    model: String,
+    messages: Vec<OllamaMessage>,
+    stream: bool,
+    options: OllamaOptions,
+}
+
+#[derive(Serialize)]
+struct OllamaOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct OllamaMessage {
+    role: &'static str,
+    content: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    images: Vec<String>,
+}
+
+impl OllamaMessage {
+    fn new(turn: &Turn) -> Self {
+        // Ollama messages have no separate parts, so OCR text is appended.
+        let content = match turn.ocr_part() {
+            Some(ocr) => format!("{}\n\n{}", turn.text, ocr),
+            None => turn.text.clone(),
+        };
+        Self {
+            role: match turn.role {
+                Role::User => "user",
+                Role::Model => "assistant",
+            },
+            content,
+            images: turn.image.iter().map(|image| image.base64.clone()).collect(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct OllamaChatResponse {
+    message: OllamaResponseMessage,
+}
+
+#[derive(Deserialize)]
+struct OllamaResponseMessage {
+    content: String,
+}
+
+/// A local Ollama server; nothing leaves the machine. Needs a vision model
+/// such as `llava` or `llama3.2-vision` to make use of the screenshot, or
+/// `--text-only` for text models.
+pub struct OllamaBackend {
+    base_url: String,
+    model: String,
+    generation: GenerationConfig,
+}
+
+impl OllamaBackend {
+    pub fn new(base_url: &str, model: &str, generation: GenerationConfig) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+            generation,
+        }
+    }
+
+    fn request_body(&self, request: &ResponseRequest) -> OllamaChatRequest {
+        OllamaChatRequest {
+            model: self.model.clone(),
+            messages: request.turns().map(OllamaMessage::new).collect(),
+            stream: false,
+            options: OllamaOptions {
+                temperature: self.generation.temperature,
+                top_p: self.generation.top_p,
+                num_predict: self.generation.max_tokens,
+            },
+        }
+    }
+}
+
+impl ResponseBackend for OllamaBackend {
+    fn name(&self) -> &'static str {
+        "Ollama"
+    }
+
+    fn generate(&self, request: &ResponseRequest) -> Result<String> {
+        // Local models on CPU can take a while, especially on first load.
+        let client = reqwest::blocking::Client::new();
+        let resp = client
+            .post(format!("{}/api/chat", self.base_url))
+            .json(&self.request_body(request))
+            .timeout(Duration::from_secs(180))
+            .send()
+            .with_context(|| format!("failed to reach Ollama at {}. Is `ollama serve` running?", self.base_url))?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let error_body = resp.text().unwrap_or_else(|_| "Unable to read error body".to_string());
+            return Err(anyhow!("Ollama error: {} - {}", status, error_body));
+        }
+
+        let body: OllamaChatResponse = resp.json().context("failed to parse Ollama response")?;
+        Ok(body.message.content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::EncodedImage;
+    use serde_json::json;
+
+    fn body(history: &[Turn], current: &Turn, generation: GenerationConfig) -> serde_json::Value {
+        let backend = OllamaBackend::new("http://localhost:11434/", "llava", generation);
+        let request = ResponseRequest { history, current };
+        serde_json::to_value(backend.request_body(&request)).unwrap()
+    }
+
+    #[test]
+    fn sends_history_then_the_prompt_with_base64_images() {
+        let mut conversation = crate::ai::Conversation::new(5, 10_000);
+        conversation.record(Turn::user("Earlier question".to_string(), "", None), "Earlier answer");
+        let image = EncodedImage {
+            mime_type: "image/jpeg",
+            base64: "/9j/".to_string(),
+            width: 1,
+            height: 1,
+            bytes: 3,
+        };
+        let current = Turn::user("What is this?".to_string(), "error 42", Some(image));
+        let generation = GenerationConfig {
+            temperature: None,
+            top_p: Some(0.9),
+            max_tokens: Some(64),
+        };
+        let body = body(conversation.turns(), &current, generation);
+
+        assert_eq!(body["model"], "llava");
+        assert_eq!(body["stream"], false);
+        assert_eq!(body["options"], json!({"top_p": 0.9f32, "num_predict": 64}));
+
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0], json!({"role": "user", "content": "Earlier question"}));
+        assert_eq!(messages[1], json!({"role": "assistant", "content": "Earlier answer"}));
+        assert_eq!(messages[2]["role"], "user");
+        let content = messages[2]["content"].as_str().unwrap();
+        assert!(content.starts_with("What is this?\n\nText recognized"));
+        assert!(content.ends_with("\nerror 42"));
+        assert_eq!(messages[2]["images"], json!(["/9j/"]));
+    }
+
+    #[test]
+    fn text_only_sends_no_images() {
+        let current = Turn::user("What is this?".to_string(), "", None);
+        let body = body(&[], &current, GenerationConfig::default());
+        assert_eq!(
+            body,
+            json!({
+                "model": "llava",
+                "messages": [{"role": "user", "content": "What is this?"}],
+                "stream": false,
+                "options": {},
+            })
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::{GenerationConfig, ResponseBackend, ResponseRequest, Role, Turn};
+
+#[derive(Serialize)]
+struct ChatRequest {
+    model: String,
+    messages: Vec<ChatMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct ChatMessage {
+    role: &'static str,
+    content: Vec<ChatContentPart>,
+}
+
+impl ChatMessage {
+    fn new(turn: &Turn) -> Self {
+        let mut content = vec![ChatContentPart::Text { text: turn.text.clone() }];
+        if let Some(text) = turn.ocr_part() {
+            content.push(ChatContentPart::Text { text });
+        }
+        if let Some(image) = &turn.image {
+            content.push(ChatContentPart::ImageUrl {
+                image_url: ImageUrl {
+                    url: format!("data:{};base64,{}", image.mime_type, image.base64),
+                },
+            });
+        }
+        Self {
+            role: match turn.role {
+                Role::User => "user",
+                Role::Model => "assistant",
+            },
+            content,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ChatContentPart {
+    Text { text: String },
+    ImageUrl { image_url: ImageUrl },
+}
+
+#[derive(Serialize)]
+struct ImageUrl {
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatResponseMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatResponseMessage {
+    content: Option<String>,
+}
+
+/// Any server speaking the OpenAI `chat/completions` protocol (OpenAI itself,
+/// Azure, LM Studio, vLLM, llama.cpp server, ...).
+pub struct OpenAiBackend {
+    base_url: String,
+    model: String,
+    api_key: Option<String>,
+    generation: GenerationConfig,
+}
+
+impl OpenAiBackend {
+    pub fn new(base_url: &str, model: &str, api_key: Option<String>, generation: GenerationConfig) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+            api_key,
+            generation,
+        }
+    }
+
+    fn request_body(&self, request: &ResponseRequest) -> ChatRequest {
+        ChatRequest {
+            model: self.model.clone(),
+            messages: request.turns().map(ChatMessage::new).collect(),
+            temperature: self.generation.temperature,
+            top_p: self.generation.top_p,
+            max_tokens: self.generation.max_tokens,
+        }
+    }
+}
+
+impl ResponseBackend for OpenAiBackend {
+    fn name(&self) -> &'static str {
+        "OpenAI-compatible"
+    }
+
+    fn generate(&self, request: &ResponseRequest) -> Result<String> {
+        let client = reqwest::blocking::Client::new();
+        let mut builder = client
+            .post(format!("{}/chat/completions", self.base_url))
+            .json(&self.request_body(request))
+            .timeout(Duration::from_secs(60));
+        if let Some(key) = &self.api_key {
+            builder = builder.bearer_auth(key);
+        }
+
+        let resp = builder
+            .send()
+            .with_context(|| format!("failed to call {}", self.base_url))?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let error_body = resp.text().unwrap_or_else(|_| "Unable to read error body".to_string());
+            return Err(anyhow!("OpenAI-compatible API error: {} - {}", status, error_body));
+        }
+
+        let body: ChatResponse = resp.json().context("failed to parse chat/completions response")?;
+
+        Ok(body.choices
+            .into_iter()
+            .next()
+            .and_then(|c| c.message.content)
+            .unwrap_or_else(|| "No response from model".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::EncodedImage;
+    use serde_json::json;
+
+    fn image() -> EncodedImage {
+        EncodedImage {
+            mime_type: "image/png",
+            base64: "iVBO".to_string(),
+            width: 1,
+            height: 1,
+            bytes: 3,
+        }
+    }
+
+    fn body(history: &[Turn], current: &Turn, generation: GenerationConfig) -> serde_json::Value {
+        let backend = OpenAiBackend::new("http://localhost:8080/v1/", "gpt-test", None, generation);
+        let request = ResponseRequest { history, current };
+        serde_json::to_value(backend.request_body(&request)).unwrap()
+    }
+
+    #[test]
+    fn sends_history_then_the_prompt_with_a_data_url() {
+        let mut conversation = crate::ai::Conversation::new(5, 10_000);
+        conversation.record(Turn::user("Earlier question".to_string(), "", None), "Earlier answer");
+        let current = Turn::user("What is this?".to_string(), "error 42", Some(image()));
+        let generation = GenerationConfig {
+            temperature: Some(0.25),
+            top_p: None,
+            max_tokens: Some(100),
+        };
+        let body = body(conversation.turns(), &current, generation);
+
+        assert_eq!(body["model"], "gpt-test");
+        assert_eq!(body["temperature"], 0.25);
+        assert_eq!(body["max_tokens"], 100);
+        assert!(body.get("top_p").is_none());
+
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0], json!({"role": "user", "content": [{"type": "text", "text": "Earlier question"}]}));
+        assert_eq!(messages[1], json!({"role": "assistant", "content": [{"type": "text", "text": "Earlier answer"}]}));
+
+        let content = messages[2]["content"].as_array().unwrap();
+        assert_eq!(messages[2]["role"], "user");
+        assert_eq!(content[0], json!({"type": "text", "text": "What is this?"}));
+        assert!(content[1]["text"].as_str().unwrap().ends_with("\nerror 42"));
+        assert_eq!(
+            content[2],
+            json!({"type": "image_url", "image_url": {"url": "data:image/png;base64,iVBO"}})
+        );
+    }
+
+    #[test]
+    fn text_only_sends_no_image() {
+        let current = Turn::user("What is this?".to_string(), "", None);
+        let body = body(&[], &current, GenerationConfig::default());
+        assert_eq!(
+            body,
+            json!({
+                "model": "gpt-test",
+                "messages": [{"role": "user", "content": [{"type": "text", "text": "What is this?"}]}],
+            })
+        );
+    }
+}
@@ -1,143 +1,395 @@
 use anyhow::{anyhow, Result};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::SampleFormat;
+use cpal::{FromSample, SampleFormat, SizedSample};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
-use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+use crate::vad::{VadOptions, VadSegmenter, VadState};
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperState};
+
+/// Sample rate whisper models are trained on.
+pub const WHISPER_SAMPLE_RATE: u32 = 16_000;
 
 pub struct AudioRecording {
-    pub data: Vec<u8>,
+    /// Interleaved samples in -1.0..=1.0, whatever format the device delivered.
+    pub samples: Vec<f32>,
     pub sample_rate: u32,
     pub channels: u16,
-    pub sample_format: SampleFormat,
 }
 
-pub fn record_audio(duration: Duration) -> Result<AudioRecording> {
-    let host = cpal::default_host();
-    let device = host
-        .default_input_device()
-        .ok_or_else(|| anyhow!("no input device available"))?;
+impl AudioRecording {
+    pub fn to_mono(&self) -> Vec<f32> {
+        downmix_to_mono(&self.samples, self.channels as usize)
+    }
+}
 
-    let config = device.default_input_config()?;
+/// Records for `duration`, or until `stop` is set.
+pub fn record_audio(input: &InputOptions, duration: Duration, stop: &AtomicBool) -> Result<AudioRecording> {
+    let (device, config) = open_input(input)?;
     let sample_rate = config.sample_rate().0;
     let channels = config.channels();
-    let sample_format = config.sample_format();
 
     let buffer = Arc::new(Mutex::new(Vec::new()));
     let buffer_clone = buffer.clone();
     let start_time = Instant::now();
-    let duration_clone = duration;
-
-    let stream = match sample_format {
-        SampleFormat::I16 => device.build_input_stream(
-            &config.into(),
-            move |data: &[i16], _: &_| {
-                if start_time.elapsed() < duration_clone {
-                    let mut buf = buffer_clone.lock().unwrap();
-                    for &sample in data {
-                        buf.extend_from_slice(&sample.to_le_bytes());
-                    }
-                }
-            },
-            move |err| eprintln!("stream error: {err}"),
-            None,
-        )?,
-        SampleFormat::U16 => device.build_input_stream(
-            &config.into(),
-            move |data: &[u16], _: &_| {
-                if start_time.elapsed() < duration_clone {
-                    let mut buf = buffer_clone.lock().unwrap();
-                    for &sample in data {
-                        buf.extend_from_slice(&sample.to_le_bytes());
-                    }
-                }
-            },
-            move |err| eprintln!("stream error: {err}"),
-            None,
-        )?,
-        SampleFormat::F32 => device.build_input_stream(
-            &config.into(),
-            move |data: &[f32], _: &_| {
-                if start_time.elapsed() < duration_clone {
-                    let mut buf = buffer_clone.lock().unwrap();
-                    for &sample in data {
-                        buf.extend_from_slice(&sample.to_le_bytes());
-                    }
-                }
-            },
-            move |err| eprintln!("stream error: {err}"),
-            None,
-        )?,
-        _ => return Err(anyhow!("unsupported sample format: {sample_format}")),
-    };
+    let stream = build_f32_input_stream(&device, &config, move |data| {
+        if start_time.elapsed() < duration {
+            buffer_clone.lock().unwrap().extend_from_slice(data);
+        }
+    })?;
 
     stream.play()?;
-    std::thread::sleep(duration);
+    while start_time.elapsed() < duration && !stop.load(Ordering::SeqCst) {
+        std::thread::sleep(Duration::from_millis(20));
+    }
     drop(stream);
 
-    let data = Arc::try_unwrap(buffer)
+    let samples = Arc::try_unwrap(buffer)
         .map_err(|_| anyhow!("failed to unwrap Arc"))?
         .into_inner()
         .unwrap();
 
     Ok(AudioRecording {
-        data,
+        samples,
         sample_rate,
         channels,
-        sample_format,
     })
 }
 
-pub fn downmix_to_mono_f32(data: &[u8], channels: u16, sample_format: SampleFormat) -> Vec<f32> {
-    let ch = channels as usize;
-    match sample_format {
-        SampleFormat::I16 => {
-            let samples: Vec<i16> = data
-                .chunks_exact(2)
-                .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]))
-                .collect();
-            to_mono_f32_i16(&samples, ch)
+/// Records from the device selected by `input` while voice activity
+/// detection says someone is talking: capture starts with the first speech
+/// frame and stops after `silence_timeout` of quiet or `max_duration`,
+/// whichever comes first, or when `stop` is set.
+///
+/// The returned recording is mono f32.
+pub fn record_with_vad(input: &InputOptions, options: &VadOptions, stop: &AtomicBool) -> Result<AudioRecording> {
+    let (device, config) = open_input(input)?;
+    let sample_rate = config.sample_rate().0;
+    let channels = config.channels() as usize;
+
+    let segmenter = Arc::new(Mutex::new(VadSegmenter::new(sample_rate, options)));
+    let segmenter_clone = segmenter.clone();
+    let stream = build_f32_input_stream(&device, &config, move |data| {
+        let mono = downmix_to_mono(data, channels);
+        segmenter_clone.lock().unwrap().push(&mono);
+    })?;
+    stream.play()?;
+
+    // The callback enforces the timeouts on audio time; this is only a safety
+    // net in case the device stops delivering samples.
+    let deadline = Instant::now() + options.start_timeout + options.max_duration + Duration::from_secs(1);
+    let mut state = segmenter.lock().unwrap().state();
+    while matches!(state, VadState::WaitingForSpeech | VadState::Speaking)
+        && Instant::now() < deadline
+        && !stop.load(Ordering::SeqCst)
+    {
+        std::thread::sleep(Duration::from_millis(20));
+        state = segmenter.lock().unwrap().state();
+    }
+    drop(stream);
+
+    match state {
+        VadState::WaitingForSpeech if stop.load(Ordering::SeqCst) => return Err(anyhow!("cancelled")),
+        VadState::WaitingForSpeech | VadState::TimedOut => {
+            return Err(anyhow!(
+                "no speech detected within {}s",
+                options.start_timeout.as_secs()
+            ))
         }
-        SampleFormat::U16 => {
-            let samples: Vec<u16> = data
-                .chunks_exact(2)
-                .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
-                .collect();
-            to_mono_f32_u16(&samples, ch)
+        VadState::Speaking | VadState::Done => {}
+    }
+
+    let speech = Arc::try_unwrap(segmenter)
+        .map_err(|_| anyhow!("failed to unwrap Arc"))?
+        .into_inner()
+        .unwrap()
+        .into_speech();
+
+    Ok(AudioRecording {
+        samples: speech,
+        sample_rate,
+        channels: 1,
+    })
+}
+
+/// Push-to-talk input that keeps the microphone stream open between
+/// captures, so audio is buffered from the moment the hotkey goes down
+/// rather than once the device has opened. Audio is only kept while
+/// `talking` is set; each hold becomes one segment.
+pub struct PushToTalkRecorder {
+    _stream: cpal::Stream,
+    state: Arc<Mutex<Segments>>,
+    talking: Arc<AtomicBool>,
+    sample_rate: u32,
+}
+
+#[derive(Default)]
+struct Segments {
+    /// Mono audio per hold, oldest first; the last one is still growing while `open`.
+    held: VecDeque<Vec<f32>>,
+    open: bool,
+}
+
+impl PushToTalkRecorder {
+    pub fn new(input: &InputOptions, talking: Arc<AtomicBool>) -> Result<Self> {
+        let (device, config) = open_input(input)?;
+        let sample_rate = config.sample_rate().0;
+        let channels = config.channels() as usize;
+
+        let state = Arc::new(Mutex::new(Segments::default()));
+        let (callback_state, callback_talking) = (state.clone(), talking.clone());
+        let stream = build_f32_input_stream(&device, &config, move |data| {
+            let talking = callback_talking.load(Ordering::SeqCst);
+            let mut state = callback_state.lock().unwrap();
+            if !talking {
+                state.open = false;
+                return;
+            }
+            if !state.open {
+                state.open = true;
+                state.held.push_back(Vec::new());
+            }
+            // Empty after a segment was taken while the key was still held:
+            // the rest of that hold is dropped.
+            if let Some(segment) = state.held.back_mut() {
+                segment.extend(downmix_to_mono(data, channels));
+            }
+        })?;
+        stream.play()?;
+
+        Ok(Self {
+            _stream: stream,
+            state,
+            talking,
+            sample_rate,
+        })
+    }
+
+    /// Waits for the oldest hold to end, or for `cancelled` or `max_duration`,
+    /// and returns its audio as a mono recording.
+    pub fn take<F>(&self, cancelled: F, max_duration: Duration) -> Result<AudioRecording>
+    where
+        F: Fn() -> bool,
+    {
+        let deadline = Instant::now() + max_duration;
+        while !cancelled() && Instant::now() < deadline {
+            let state = self.state.lock().unwrap();
+            let waiting = match state.held.len() {
+                // The key went down but no audio has arrived yet.
+                0 => self.talking.load(Ordering::SeqCst),
+                1 => state.open,
+                _ => false,
+            };
+            if !waiting {
+                break;
+            }
+            drop(state);
+            std::thread::sleep(Duration::from_millis(20));
         }
-        SampleFormat::F32 => {
-            let samples: Vec<f32> = data
-                .chunks_exact(4)
-                .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-                .collect();
-            to_mono_f32_f32(&samples, ch)
+
+        let samples = self.state.lock().unwrap().held.pop_front().unwrap_or_default();
+        if samples.is_empty() {
+            return Err(anyhow!("nothing recorded: the hotkey was released before any audio arrived"));
         }
-        _ => Vec::new(),
+        Ok(AudioRecording {
+            samples,
+            sample_rate: self.sample_rate,
+            channels: 1,
+        })
     }
 }
 
-fn to_mono_f32_i16(samples: &[i16], channels: usize) -> Vec<f32> {
-    let mut mono = Vec::with_capacity(samples.len() / channels);
-    for chunk in samples.chunks(channels) {
-        let sum: f32 = chunk.iter().map(|&s| s as f32 / i16::MAX as f32).sum();
-        mono.push(sum / channels as f32);
+/// Which microphone to record from, and optionally in which format.
+#[derive(Clone, Debug, Default)]
+pub struct InputOptions {
+    /// Index from `--list-devices` or (part of) a device name; the system
+    /// default input when unset.
+    pub device: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u16>,
+}
+
+/// Every input device of every available audio host, in `--list-devices` order.
+fn input_devices() -> Vec<(cpal::HostId, cpal::Device)> {
+    let mut devices = Vec::new();
+    for host_id in cpal::available_hosts() {
+        let Ok(host) = cpal::host_from_id(host_id) else {
+            continue;
+        };
+        if let Ok(inputs) = host.input_devices() {
+            devices.extend(inputs.map(|device| (host_id, device)));
+        }
     }
-    mono
+    devices
 }
 
-fn to_mono_f32_u16(samples: &[u16], channels: usize) -> Vec<f32> {
-    let mut mono = Vec::with_capacity(samples.len() / channels);
-    for chunk in samples.chunks(channels) {
-        let sum: f32 = chunk
-            .iter()
-            .map(|&s| (s as f32 - 32768.0) / 32768.0)
-            .sum();
-        mono.push(sum / channels as f32);
+fn device_name(device: &cpal::Device) -> String {
+    device.name().unwrap_or_else(|_| "(unnamed device)".to_string())
+}
+
+/// Opens the selected input device with the requested or its default stream config.
+pub fn open_input(options: &InputOptions) -> Result<(cpal::Device, cpal::SupportedStreamConfig)> {
+    let device = match &options.device {
+        None => cpal::default_host()
+            .default_input_device()
+            .ok_or_else(|| anyhow!("no input device available"))?,
+        Some(selection) => find_device(selection)?,
+    };
+    let config = input_config(&device, options)?;
+    Ok((device, config))
+}
+
+/// Looks a device up by index, exact name or unique part of its name.
+fn find_device(selection: &str) -> Result<cpal::Device> {
+    let mut devices = input_devices();
+    if let Ok(index) = selection.parse::<usize>() {
+        if index >= devices.len() {
+            return Err(anyhow!("no input device {} (found {}); see --list-devices", index, devices.len()));
+        }
+        return Ok(devices.swap_remove(index).1);
     }
-    mono
+
+    let wanted = selection.to_lowercase();
+    if let Some(index) = devices.iter().position(|(_, device)| device_name(device).to_lowercase() == wanted) {
+        return Ok(devices.swap_remove(index).1);
+    }
+    let mut matches: Vec<cpal::Device> = devices
+        .into_iter()
+        .map(|(_, device)| device)
+        .filter(|device| device_name(device).to_lowercase().contains(&wanted))
+        .collect();
+    match matches.len() {
+        0 => Err(anyhow!("no input device matching '{}'; see --list-devices", selection)),
+        1 => Ok(matches.remove(0)),
+        _ => {
+            let names: Vec<String> = matches.iter().map(device_name).collect();
+            Err(anyhow!(
+                "'{}' matches several input devices: {}. Use a longer name or the index from --list-devices",
+                selection,
+                names.join(", ")
+            ))
+        }
+    }
+}
+
+/// The device's default config, or a supported one with the requested sample
+/// rate and channel count (missing values taken from the default).
+fn input_config(device: &cpal::Device, options: &InputOptions) -> Result<cpal::SupportedStreamConfig> {
+    let default = device.default_input_config()?;
+    if options.sample_rate.is_none() && options.channels.is_none() {
+        return Ok(default);
+    }
+
+    let sample_rate = options.sample_rate.unwrap_or(default.sample_rate().0);
+    let channels = options.channels.unwrap_or(default.channels());
+    device
+        .supported_input_configs()?
+        .filter(|range| {
+            range.channels() == channels
+                && range.min_sample_rate().0 <= sample_rate
+                && sample_rate <= range.max_sample_rate().0
+        })
+        // Prefer the device's usual sample format.
+        .max_by_key(|range| range.sample_format() == default.sample_format())
+        .map(|range| range.with_sample_rate(cpal::SampleRate(sample_rate)))
+        .ok_or_else(|| {
+            anyhow!(
+                "{} does not support {} channel(s) at {} Hz; see --list-devices",
+                device_name(device),
+                channels,
+                sample_rate
+            )
+        })
+}
+
+/// One line per host and input device with its supported configs, for `--list-devices`.
+pub fn describe_input_devices() -> Result<Vec<String>> {
+    let default_host = cpal::default_host();
+    let default_name = default_host.default_input_device().map(|device| device_name(&device));
+
+    let mut lines = Vec::new();
+    let mut current_host = None;
+    for (index, (host_id, device)) in input_devices().into_iter().enumerate() {
+        if current_host != Some(host_id) {
+            current_host = Some(host_id);
+            lines.push(format!("{}:", host_id.name()));
+        }
+        let name = device_name(&device);
+        let is_default = host_id == default_host.id() && default_name.as_deref() == Some(name.as_str());
+        lines.push(format!("  {}: {}{}", index, name, if is_default { " (default)" } else { "" }));
+        match device.supported_input_configs() {
+            Ok(ranges) => {
+                for range in ranges {
+                    let (min, max) = (range.min_sample_rate().0, range.max_sample_rate().0);
+                    let rates = if min == max { format!("{} Hz", min) } else { format!("{}-{} Hz", min, max) };
+                    lines.push(format!("       {} ch, {}, {}", range.channels(), rates, range.sample_format()));
+                }
+            }
+            Err(e) => lines.push(format!("       (configs unavailable: {})", e)),
+        }
+    }
+    if lines.is_empty() {
+        return Err(anyhow!("no audio input devices found"));
+    }
+    Ok(lines)
 }
 
-fn to_mono_f32_f32(samples: &[f32], channels: usize) -> Vec<f32> {
+/// Builds an input stream that converts every callback buffer to interleaved f32
+/// before handing it to `on_data`, whatever the device's native sample format is.
+pub fn build_f32_input_stream<F>(
+    device: &cpal::Device,
+    config: &cpal::SupportedStreamConfig,
+    on_data: F,
+) -> Result<cpal::Stream>
+where
+    F: FnMut(&[f32]) + Send + 'static,
+{
+    let stream_config: cpal::StreamConfig = config.clone().into();
+    match config.sample_format() {
+        SampleFormat::I8 => build_converting_stream::<i8, F>(device, &stream_config, on_data),
+        SampleFormat::I16 => build_converting_stream::<i16, F>(device, &stream_config, on_data),
+        SampleFormat::I32 => build_converting_stream::<i32, F>(device, &stream_config, on_data),
+        SampleFormat::I64 => build_converting_stream::<i64, F>(device, &stream_config, on_data),
+        SampleFormat::U8 => build_converting_stream::<u8, F>(device, &stream_config, on_data),
+        SampleFormat::U16 => build_converting_stream::<u16, F>(device, &stream_config, on_data),
+        SampleFormat::U32 => build_converting_stream::<u32, F>(device, &stream_config, on_data),
+        SampleFormat::U64 => build_converting_stream::<u64, F>(device, &stream_config, on_data),
+        SampleFormat::F32 => build_converting_stream::<f32, F>(device, &stream_config, on_data),
+        SampleFormat::F64 => build_converting_stream::<f64, F>(device, &stream_config, on_data),
+        other => Err(anyhow!("unsupported sample format: {other}")),
+    }
+}
+
+fn build_converting_stream<T, F>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut on_data: F,
+) -> Result<cpal::Stream>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+    F: FnMut(&[f32]) + Send + 'static,
+{
+    let mut converted = Vec::new();
+    let stream = device.build_input_stream(
+        config,
+        move |data: &[T], _: &_| {
+            converted.clear();
+            converted.extend(data.iter().map(|&s| s.to_sample::<f32>()));
+            on_data(&converted);
+        },
+        move |err| eprintln!("stream error: {err}"),
+        None,
+    )?;
+    Ok(stream)
+}
+
+/// Averages interleaved channels into one.
+pub fn downmix_to_mono(samples: &[f32], channels: usize) -> Vec<f32> {
+    if channels <= 1 {
+        return samples.to_vec();
+    }
     let mut mono = Vec::with_capacity(samples.len() / channels);
     for chunk in samples.chunks(channels) {
         let sum: f32 = chunk.iter().sum();
@@ -146,44 +398,59 @@ fn to_mono_f32_f32(samples: &[f32], channels: usize) -> Vec<f32> {
     mono
 }
 
-pub fn linear_resample(input: &[f32], src_sr: u32, dst_sr: u32) -> Vec<f32> {
-    if src_sr == 0 || dst_sr == 0 || input.is_empty() {
-        return Vec::new();
+pub fn transcribe_audio(model_path: &str, audio_data: &[f32], language: &str) -> Result<String> {
+    Transcriber::new(model_path)?.transcribe(audio_data, language)
+}
+
+/// Keeps a whisper model loaded so repeated captures (e.g. every hotkey press
+/// in listener mode) do not pay for reading the model from disk again.
+pub struct Transcriber {
+    ctx: WhisperContext,
+    state: Option<WhisperState>,
+}
+
+impl Transcriber {
+    pub fn new(model_path: &str) -> Result<Self> {
+        let ctx = WhisperContext::new_with_params(
+            model_path,
+            WhisperContextParameters::default(),
+        )
+        .map_err(|e| anyhow!("failed to load model: {e}"))?;
+
+        Ok(Self { ctx, state: None })
     }
-    if src_sr == dst_sr {
-        return input.to_vec();
+
+    /// Runs one pass over a second of silence so the whisper state and its
+    /// buffers are allocated before the first real capture.
+    pub fn warm_up(&mut self, language: &str) -> Result<()> {
+        let silence = vec![0.0f32; WHISPER_SAMPLE_RATE as usize];
+        self.transcribe(&silence, language).map(|_| ())
     }
-    let src_len = input.len() as f32;
-    let ratio = dst_sr as f32 / src_sr as f32;
-    let out_len = (src_len * ratio).ceil() as usize;
-    let mut out = Vec::with_capacity(out_len);
-    for n in 0..out_len {
-        let pos = n as f32 / ratio;
-        let i0 = pos.floor() as usize;
-        let i1 = (i0 + 1).min(input.len() - 1);
-        let frac = pos - i0 as f32;
-        let y = input[i0] * (1.0 - frac) + input[i1] * frac;
-        out.push(y);
+
+    pub fn transcribe(&mut self, audio_data: &[f32], language: &str) -> Result<String> {
+        let state = match &mut self.state {
+            Some(state) => state,
+            None => self.state.insert(
+                self.ctx
+                    .create_state()
+                    .map_err(|e| anyhow!("failed to create whisper state: {e}"))?,
+            ),
+        };
+        run_whisper(state, audio_data, language)
     }
-    out
 }
 
-pub fn transcribe_audio(model_path: &str, audio_data: &[f32], language: &str) -> Result<String> {
-    let ctx = WhisperContext::new_with_params(
-        model_path,
-        WhisperContextParameters::default(),
-    )
-    .map_err(|e| anyhow!("failed to load model: {e}"))?;
-    
-    let mut state = ctx
-        .create_state()
-        .map_err(|e| anyhow!("failed to create whisper state: {e}"))?;
-
+/// Runs a full whisper pass over 16 kHz mono audio on an existing state and
+/// returns the non-empty segments joined with spaces.
+pub fn run_whisper(state: &mut WhisperState, audio_data: &[f32], language: &str) -> Result<String> {
     let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
     params.set_n_threads(std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4) as i32);
     params.set_language(Some(language));
     params.set_translate(false);
     params.set_no_context(true);
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_print_timestamps(false);
 
     state
         .full(params, audio_data)
@@ -201,4 +468,3 @@ pub fn transcribe_audio(model_path: &str, audio_data: &[f32], language: &str) ->
 
     Ok(text)
 }
-
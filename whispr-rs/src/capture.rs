@@ -0,0 +1,339 @@
+use anyhow::{anyhow, Result};
+use image::imageops::{self, FilterType};
+use image::RgbaImage;
+use screenshots::Screen;
+use std::str::FromStr;
+
+use crate::window::{active_window, ActiveWindow};
+
+/// Which monitor(s) to capture.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DisplaySelection {
+    /// The display the OS marks as primary.
+    Primary,
+    /// Position in `--list-displays` order.
+    Index(usize),
+    /// Display id as reported by the OS (`id:<n>`).
+    Id(u32),
+    /// Output name such as `HDMI-1` (`name:<name>`, X11 only).
+    Name(String),
+    /// The display under the mouse cursor.
+    Cursor,
+    /// Every display stitched into one image.
+    All,
+    /// Only the focused window.
+    Window,
+}
+
+impl FromStr for DisplaySelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(name) = s.trim().strip_prefix("name:") {
+            if name.trim().is_empty() {
+                return Err("display name must not be empty".to_string());
+            }
+            return Ok(Self::Name(name.trim().to_string()));
+        }
+        match s.trim().to_ascii_lowercase().as_str() {
+            "primary" => Ok(Self::Primary),
+            "cursor" | "mouse" => Ok(Self::Cursor),
+            "all" => Ok(Self::All),
+            "window" | "active" => Ok(Self::Window),
+            other => {
+                if let Some(id) = other.strip_prefix("id:") {
+                    return id.parse().map(Self::Id).map_err(|_| format!("invalid display id '{}'", id));
+                }
+                other.parse().map(Self::Index).map_err(|_| {
+                    format!("invalid display '{}': expected an index, id:<n>, name:<name>, primary, cursor, all or window", s)
+                })
+            }
+        }
+    }
+}
+
+/// A rectangle relative to the top-left corner of the captured display(s)
+/// or window, in logical pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl FromStr for Region {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(',').map(str::trim).collect();
+        let [x, y, width, height] = parts[..] else {
+            return Err(format!("invalid region '{}': expected x,y,width,height", s));
+        };
+        let invalid = |_| format!("invalid region '{}': expected x,y,width,height", s);
+        let region = Region {
+            x: x.parse().map_err(invalid)?,
+            y: y.parse().map_err(invalid)?,
+            width: width.parse().map_err(invalid)?,
+            height: height.parse().map_err(invalid)?,
+        };
+        if region.width == 0 || region.height == 0 {
+            return Err(format!("invalid region '{}': width and height must be positive", s));
+        }
+        Ok(region)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CaptureOptions {
+    pub display: DisplaySelection,
+    /// Crop to this rectangle of the selected display(s) or window.
+    pub region: Option<Region>,
+    /// Last known mouse position in desktop coordinates, for
+    /// [`DisplaySelection::Cursor`]. The primary display is used without it.
+    pub cursor: Option<(i32, i32)>,
+}
+
+pub struct Screenshot {
+    pub image: RgbaImage,
+    /// Set when only the focused window was captured.
+    pub window: Option<ActiveWindow>,
+}
+
+/// Takes a screenshot according to `options`.
+pub fn capture_screen(options: &CaptureOptions) -> Result<Screenshot> {
+    let (screens, window) = select_displays(Screen::all()?, options)?;
+    let mut image = capture_displays(&screens)?;
+    let (left, top, mut logical_width, _) = bounds(&screens);
+
+    if let Some(window) = &window {
+        let area = Region {
+            x: window.x.saturating_sub(left),
+            y: window.y.saturating_sub(top),
+            width: window.width,
+            height: window.height,
+        };
+        image = crop(&image, logical_width, area)?;
+        logical_width = area.width;
+    }
+    if let Some(region) = options.region {
+        image = crop(&image, logical_width, region)?;
+    }
+
+    Ok(Screenshot { image, window })
+}
+
+/// One line per display for `--list-displays`.
+pub fn describe_displays() -> Result<Vec<String>> {
+    let screens = Screen::all()?;
+    let names = display_names(&screens).unwrap_or_default();
+    Ok(screens
+        .iter()
+        .enumerate()
+        .map(|(index, screen)| {
+            let info = screen.display_info;
+            format!(
+                "{}: id:{}{} {}x{} at {},{} scale {}{}",
+                index,
+                info.id,
+                names
+                    .get(index)
+                    .map(|name| format!(" name:{}", name))
+                    .unwrap_or_default(),
+                info.width,
+                info.height,
+                info.x,
+                info.y,
+                info.scale_factor,
+                if info.is_primary { " (primary)" } else { "" }
+            )
+        })
+        .collect())
+}
+
+/// The displays to capture, plus the focused window when only that is wanted.
+fn select_displays(screens: Vec<Screen>, options: &CaptureOptions) -> Result<(Vec<Screen>, Option<ActiveWindow>)> {
+    if screens.is_empty() {
+        return Err(anyhow!("no screens detected for screenshot"));
+    }
+    let primary = || {
+        screens
+            .iter()
+            .find(|s| s.display_info.is_primary)
+            .unwrap_or(&screens[0])
+            .to_owned()
+    };
+
+    let screen = match &options.display {
+        DisplaySelection::All => return Ok((screens, None)),
+        DisplaySelection::Window => {
+            let window = active_window()?;
+            let under: Vec<Screen> = screens.into_iter().filter(|s| overlaps(s, &window)).collect();
+            if under.is_empty() {
+                return Err(anyhow!("focused window '{}' is not on any display", window.title));
+            }
+            return Ok((under, Some(window)));
+        }
+        DisplaySelection::Primary => primary(),
+        DisplaySelection::Index(index) => *screens.get(*index).ok_or_else(|| {
+            anyhow!("display {} not found, {} available (see --list-displays)", index, screens.len())
+        })?,
+        DisplaySelection::Id(id) => *screens
+            .iter()
+            .find(|s| s.display_info.id == *id)
+            .ok_or_else(|| anyhow!("no display with id {} (see --list-displays)", id))?,
+        DisplaySelection::Name(name) => {
+            let names = display_names(&screens)?;
+            let index = names
+                .iter()
+                .position(|n| n.eq_ignore_ascii_case(name))
+                .ok_or_else(|| anyhow!("no display named '{}' (see --list-displays)", name))?;
+            screens[index]
+        }
+        DisplaySelection::Cursor => match options.cursor {
+            Some((x, y)) => screens
+                .iter()
+                .find(|s| contains(s, x, y))
+                .copied()
+                .unwrap_or_else(primary),
+            None => primary(),
+        },
+    };
+    Ok((vec![screen], None))
+}
+
+/// RandR output names (`HDMI-1`, `eDP-1`, ...) in the same order as `screens`.
+#[cfg(target_os = "linux")]
+fn display_names(screens: &[Screen]) -> Result<Vec<String>> {
+    use xcb::randr;
+
+    let (conn, _) = xcb::Connection::connect(None)
+        .map_err(|e| anyhow!("failed to connect to the X server: {}", e))?;
+    screens
+        .iter()
+        .map(|screen| {
+            let cookie = conn.send_request(&randr::GetOutputInfo {
+                output: screen.display_info.raw_handle,
+                config_timestamp: xcb::x::CURRENT_TIME,
+            });
+            let reply = conn.wait_for_reply(cookie)?;
+            Ok(String::from_utf8_lossy(reply.name()).into_owned())
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn display_names(_screens: &[Screen]) -> Result<Vec<String>> {
+    Err(anyhow!("display names are only available on Linux (X11); use an index or id:<n>"))
+}
+
+fn contains(screen: &Screen, x: i32, y: i32) -> bool {
+    let (left, top, right, bottom) = edges(screen);
+    let (x, y) = (x as i64, y as i64);
+    x >= left && x < right && y >= top && y < bottom
+}
+
+fn overlaps(screen: &Screen, window: &ActiveWindow) -> bool {
+    let (left, top, right, bottom) = edges(screen);
+    let (x, y) = (window.x as i64, window.y as i64);
+    x < right && x + window.width as i64 > left && y < bottom && y + window.height as i64 > top
+}
+
+/// Left, top, right and bottom edge of a display. Computed in i64 so huge
+/// widths or far-off positions cannot overflow.
+fn edges(screen: &Screen) -> (i64, i64, i64, i64) {
+    let info = screen.display_info;
+    let (x, y) = (info.x as i64, info.y as i64);
+    (x, y, x + info.width as i64, y + info.height as i64)
+}
+
+/// Bounding box of the displays as (left, top, width, height) in logical pixels.
+fn bounds(screens: &[Screen]) -> (i32, i32, u32, u32) {
+    let left = screens.iter().map(|s| s.display_info.x).min().unwrap_or(0);
+    let top = screens.iter().map(|s| s.display_info.y).min().unwrap_or(0);
+    let right = screens.iter().map(|s| edges(s).2).max().unwrap_or(0);
+    let bottom = screens.iter().map(|s| edges(s).3).max().unwrap_or(0);
+    let span = |from: i32, to: i64| (to - from as i64).clamp(0, u32::MAX as i64) as u32;
+    (left, top, span(left, right), span(top, bottom))
+}
+
+/// Captures each display and places it at its desktop position. Displays
+/// with a lower scale factor are upscaled so the whole image shares the
+/// highest one and no detail is lost on HiDPI screens.
+fn capture_displays(screens: &[Screen]) -> Result<RgbaImage> {
+    if let [screen] = screens {
+        return screen.capture();
+    }
+
+    let scale = screens
+        .iter()
+        .map(|s| s.display_info.scale_factor)
+        .fold(1.0, f32::max);
+    let scaled = |v: f32| (v * scale).round();
+    let (left, top, width, height) = bounds(screens);
+    let mut canvas = RgbaImage::new(scaled(width as f32) as u32, scaled(height as f32) as u32);
+
+    for screen in screens {
+        let info = screen.display_info;
+        let mut image = screen.capture()?;
+        let size = (scaled(info.width as f32) as u32, scaled(info.height as f32) as u32);
+        if image.dimensions() != size {
+            image = imageops::resize(&image, size.0, size.1, FilterType::Triangle);
+        }
+        imageops::overlay(
+            &mut canvas,
+            &image,
+            scaled((info.x - left) as f32) as i64,
+            scaled((info.y - top) as f32) as i64,
+        );
+    }
+    Ok(canvas)
+}
+
+fn crop(image: &RgbaImage, logical_width: u32, region: Region) -> Result<RgbaImage> {
+    // Captures are in physical pixels; the region is in logical ones.
+    let scale = image.width() as f32 / logical_width.max(1) as f32;
+    let to_px = |v: i64| (v as f64 * scale as f64).round() as i64;
+
+    let (x, y) = (region.x as i64, region.y as i64);
+    let x1 = to_px(x).clamp(0, image.width() as i64);
+    let y1 = to_px(y).clamp(0, image.height() as i64);
+    let x2 = to_px(x + region.width as i64).clamp(0, image.width() as i64);
+    let y2 = to_px(y + region.height as i64).clamp(0, image.height() as i64);
+    if x1 >= x2 || y1 >= y2 {
+        return Err(anyhow!(
+            "region {},{},{},{} lies outside the captured area",
+            region.x,
+            region.y,
+            region.width,
+            region.height
+        ));
+    }
+
+    Ok(imageops::crop_imm(image, x1 as u32, y1 as u32, (x2 - x1) as u32, (y2 - y1) as u32).to_image())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_display_names() {
+        assert_eq!("name:HDMI-1".parse(), Ok(DisplaySelection::Name("HDMI-1".to_string())));
+        assert_eq!("id:42".parse(), Ok(DisplaySelection::Id(42)));
+        assert_eq!("2".parse(), Ok(DisplaySelection::Index(2)));
+        assert!("name:".parse::<DisplaySelection>().is_err());
+        assert!("HDMI-1".parse::<DisplaySelection>().is_err());
+    }
+
+    #[test]
+    fn crop_handles_regions_past_i32_max() {
+        let image = RgbaImage::new(200, 100);
+        let region = Region { x: i32::MAX - 5, y: 0, width: u32::MAX, height: 10 };
+        assert!(crop(&image, 100, region).is_err());
+
+        let region = Region { x: -10, y: 40, width: u32::MAX, height: u32::MAX };
+        let cropped = crop(&image, 100, region).unwrap();
+        assert_eq!(cropped.dimensions(), (200, 20));
+    }
+}
@@ -0,0 +1,244 @@
+use anyhow::{bail, Context, Result};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Command};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+use crate::utils::config_dir;
+
+/// Settings from `config.toml`. Keys are the long command-line flags
+/// (`model = "..."`, `stream-response = true`), `[profiles.<name>]` tables
+/// override them for `--profile <name>`, and a top-level `profile` key picks
+/// the profile used when none is given.
+pub struct Config {
+    pub path: PathBuf,
+    settings: Table,
+    profiles: Table,
+    default_profile: Option<String>,
+}
+
+impl Config {
+    /// Reads `path`, or `config.toml` in the config dir if there is one.
+    pub fn load(path: Option<&Path>) -> Result<Option<Self>> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match config_dir().map(|dir| dir.join("config.toml")) {
+                Some(path) if path.exists() => path,
+                _ => return Ok(None),
+            },
+        };
+
+        let source = std::fs::read_to_string(&path)
+            .with_context(|| format!("failed to read config file {}", path.display()))?;
+        let mut settings: Table = toml::from_str(&source)
+            .with_context(|| format!("failed to parse config file {}", path.display()))?;
+
+        let profiles = match settings.remove("profiles") {
+            Some(Value::Table(profiles)) => profiles,
+            Some(_) => bail!("'profiles' in {} must be a table of [profiles.<name>] sections", path.display()),
+            None => Table::new(),
+        };
+        let default_profile = match settings.remove("profile") {
+            Some(Value::String(name)) => Some(name),
+            Some(_) => bail!("'profile' in {} must be a profile name", path.display()),
+            None => None,
+        };
+
+        Ok(Some(Self {
+            path,
+            settings,
+            profiles,
+            default_profile,
+        }))
+    }
+
+    /// The top-level settings with the selected profile's applied on top.
+    pub fn settings(&self, profile: Option<&str>) -> Result<Table> {
+        let mut settings = self.settings.clone();
+        let Some(name) = profile.or(self.default_profile.as_deref()) else {
+            return Ok(settings);
+        };
+
+        match self.profiles.get(name) {
+            Some(Value::Table(profile)) => {
+                settings.extend(profile.clone());
+                Ok(settings)
+            }
+            Some(_) => bail!("profile '{}' in {} must be a [profiles.{}] table", name, self.path.display(), name),
+            None => {
+                let names: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+                bail!(
+                    "unknown profile '{}'. Profiles in {}: {}",
+                    name,
+                    self.path.display(),
+                    if names.is_empty() { "(none)".to_string() } else { names.join(", ") }
+                )
+            }
+        }
+    }
+}
+
+/// Turns config settings into command-line arguments. Settings whose flag
+/// was already given on the command line or through an environment variable
+/// are skipped, so those keep precedence.
+pub fn settings_to_args(command: &Command, matches: &ArgMatches, settings: &Table) -> Result<Vec<OsString>> {
+    let mut args = Vec::new();
+    for (key, value) in settings {
+        let long = key.replace('_', "-");
+        let Some(arg) = command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(long.as_str()) && !matches!(long.as_str(), "config" | "profile"))
+        else {
+            bail!("unknown setting '{}'", key);
+        };
+        if matches!(
+            matches.value_source(arg.get_id().as_str()),
+            Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+        ) {
+            continue;
+        }
+
+        let values = match value {
+            Value::Array(values) => values.as_slice(),
+            value => std::slice::from_ref(value),
+        };
+        for value in values {
+            let value = match value {
+                Value::Boolean(set) if !arg.get_action().takes_values() => {
+                    if *set {
+                        args.push(format!("--{}", long).into());
+                    }
+                    continue;
+                }
+                Value::String(text) => text.clone(),
+                Value::Integer(_) | Value::Float(_) | Value::Boolean(_) => value.to_string(),
+                _ => bail!("unsupported value for setting '{}'", key),
+            };
+            args.push(format!("--{}={}", long, value).into());
+        }
+    }
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{value_parser, Arg, ArgAction};
+
+    fn command() -> Command {
+        Command::new("whispr-rs")
+            .arg(Arg::new("model").long("model").default_value("base"))
+            .arg(Arg::new("language").long("language").env("WHISPR_TEST_LANGUAGE").default_value("en"))
+            .arg(Arg::new("duration").long("duration").value_parser(value_parser!(u64)).default_value("5"))
+            .arg(Arg::new("stream_response").long("stream-response").action(ArgAction::SetTrue))
+            .arg(Arg::new("bind").long("bind").action(ArgAction::Append))
+            .arg(Arg::new("redact_pattern").long("redact-pattern").action(ArgAction::Append))
+            .arg(Arg::new("config").long("config"))
+            .arg(Arg::new("profile").long("profile"))
+    }
+
+    fn load(source: &str) -> Config {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, source).unwrap();
+        Config::load(Some(&path)).unwrap().unwrap()
+    }
+
+    /// Does what `load_args` does: config settings first, then the real
+    /// command line, parsed again.
+    fn resolve(source: &str, profile: Option<&str>, cli: &[&str]) -> Result<ArgMatches> {
+        let config = load(source);
+        let cli: Vec<OsString> = std::iter::once("whispr-rs").chain(cli.iter().copied()).map(Into::into).collect();
+        let matches = command().try_get_matches_from(&cli)?;
+        let mut argv = vec![cli[0].clone()];
+        argv.extend(settings_to_args(&command(), &matches, &config.settings(profile)?)?);
+        argv.extend(cli.into_iter().skip(1));
+        Ok(command().try_get_matches_from(argv)?)
+    }
+
+    fn value<'a>(matches: &'a ArgMatches, id: &str) -> &'a str {
+        matches.get_one::<String>(id).unwrap()
+    }
+
+    #[test]
+    fn settings_fill_in_defaults() {
+        let matches = resolve("model = \"small\"\nduration = 12\nstream_response = true", None, &[]).unwrap();
+        assert_eq!(value(&matches, "model"), "small");
+        assert_eq!(matches.get_one::<u64>("duration"), Some(&12));
+        assert!(matches.get_flag("stream_response"));
+    }
+
+    #[test]
+    fn profile_overrides_top_level() {
+        let source = r#"
+            model = "small"
+            stream-response = true
+            [profiles.work]
+            model = "large"
+            stream-response = false
+        "#;
+        let matches = resolve(source, Some("work"), &[]).unwrap();
+        assert_eq!(value(&matches, "model"), "large");
+        assert!(!matches.get_flag("stream_response"));
+
+        let matches = resolve(source, None, &[]).unwrap();
+        assert_eq!(value(&matches, "model"), "small");
+        assert!(matches.get_flag("stream_response"));
+    }
+
+    #[test]
+    fn default_profile_applies_unless_overridden() {
+        let source = "profile = \"a\"\n[profiles.a]\nmodel = \"tiny\"\n[profiles.b]\nmodel = \"medium\"";
+        assert_eq!(value(&resolve(source, None, &[]).unwrap(), "model"), "tiny");
+        assert_eq!(value(&resolve(source, Some("b"), &[]).unwrap(), "model"), "medium");
+    }
+
+    #[test]
+    fn command_line_beats_config() {
+        let source = "model = \"small\"\n[profiles.work]\nmodel = \"large\"";
+        let matches = resolve(source, Some("work"), &["--model", "tiny"]).unwrap();
+        assert_eq!(value(&matches, "model"), "tiny");
+    }
+
+    #[test]
+    fn environment_beats_config() {
+        std::env::set_var("WHISPR_TEST_LANGUAGE", "de");
+        let matches = resolve("language = \"fr\"", None, &[]);
+        std::env::remove_var("WHISPR_TEST_LANGUAGE");
+        assert_eq!(value(&matches.unwrap(), "language"), "de");
+    }
+
+    #[test]
+    fn arrays_become_repeated_flags() {
+        let source = r#"
+            bind = ["f9=capture", "f10=cancel"]
+            redact-pattern = ['id=\d+']
+        "#;
+        let matches = resolve(source, None, &[]).unwrap();
+        let binds: Vec<&String> = matches.get_many("bind").unwrap().collect();
+        assert_eq!(binds, ["f9=capture", "f10=cancel"]);
+        let patterns: Vec<&String> = matches.get_many("redact_pattern").unwrap().collect();
+        assert_eq!(patterns, [r"id=\d+"]);
+
+        // Values given on the command line replace the configured list.
+        let matches = resolve(source, None, &["--bind", "f8=capture"]).unwrap();
+        let binds: Vec<&String> = matches.get_many("bind").unwrap().collect();
+        assert_eq!(binds, ["f8=capture"]);
+    }
+
+    #[test]
+    fn rejects_bad_settings() {
+        assert!(resolve("modle = \"small\"", None, &[]).is_err());
+        assert!(resolve("config = \"other.toml\"", None, &[]).is_err());
+        assert!(resolve("duration = \"long\"", None, &[]).is_err());
+        assert!(resolve("model = { name = \"small\" }", None, &[]).is_err());
+        assert!(resolve("model = \"small\"", Some("missing"), &[]).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "profiles = 3").unwrap();
+        assert!(Config::load(Some(&path)).is_err());
+        assert!(Config::load(Some(&dir.path().join("missing.toml"))).is_err());
+    }
+}
@@ -0,0 +1,234 @@
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use std::path::Path;
+
+use crate::audio::AudioRecording;
+
+/// Reads an audio file instead of recording: WAV through hound, FLAC, MP3
+/// and OGG Vorbis with the `audio-formats` feature.
+pub fn read_audio_file(path: &Path) -> Result<AudioRecording> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    let recording = match extension.as_deref() {
+        Some("wav" | "wave") => read_wav(path),
+        _ => read_compressed(path),
+    }
+    .with_context(|| format!("failed to read audio file {}", path.display()))?;
+
+    if recording.samples.is_empty() {
+        return Err(anyhow!("audio file {} contains no samples", path.display()));
+    }
+    // Resampling from an unknown rate would hand whisper audio at the wrong speed.
+    if recording.sample_rate == 0 || recording.channels == 0 {
+        return Err(anyhow!("audio file {} does not report its sample rate and channels", path.display()));
+    }
+    Ok(recording)
+}
+
+fn read_wav(path: &Path) -> Result<AudioRecording> {
+    let mut reader = hound::WavReader::open(path)?;
+    let spec = reader.spec();
+    let samples = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>()?,
+        hound::SampleFormat::Int => {
+            let scale = 1.0 / (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|s| s as f32 * scale))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+    };
+    Ok(AudioRecording {
+        samples,
+        sample_rate: spec.sample_rate,
+        channels: spec.channels,
+    })
+}
+
+#[cfg(feature = "audio-formats")]
+fn read_compressed(path: &Path) -> Result<AudioRecording> {
+    use symphonia::core::audio::SampleBuffer;
+    use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+    use symphonia::core::errors::Error;
+    use symphonia::core::formats::FormatOptions;
+    use symphonia::core::io::MediaSourceStream;
+    use symphonia::core::meta::MetadataOptions;
+    use symphonia::core::probe::Hint;
+
+    let file = std::fs::File::open(path)?;
+    let source = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|extension| extension.to_str()) {
+        hint.with_extension(extension);
+    }
+    let mut format = symphonia::default::get_probe()
+        .format(&hint, source, &FormatOptions::default(), &MetadataOptions::default())
+        .context("unsupported or unrecognised audio format")?
+        .format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| anyhow!("no audio track found"))?;
+    let track_id = track.id;
+    let mut decoder = symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+
+    let mut recording = AudioRecording {
+        samples: Vec::new(),
+        sample_rate: track.codec_params.sample_rate.unwrap_or(0),
+        channels: track.codec_params.channels.map_or(0, |channels| channels.count() as u16),
+    };
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(Error::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A corrupt frame only loses a few milliseconds; keep going.
+            Err(Error::DecodeError(_)) => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        let spec = *decoded.spec();
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+        recording.sample_rate = spec.rate;
+        recording.channels = spec.channels.count() as u16;
+        recording.samples.extend_from_slice(buffer.samples());
+    }
+    Ok(recording)
+}
+
+#[cfg(not(feature = "audio-formats"))]
+fn read_compressed(_path: &Path) -> Result<AudioRecording> {
+    Err(anyhow!(
+        "only WAV files are supported by this build; FLAC, MP3 and OGG need the `audio-formats` feature (cargo build --features audio-formats)"
+    ))
+}
+
+/// Which buffers `--save-audio` writes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SavedAudio {
+    /// The capture as the device delivered it.
+    Raw,
+    /// The 16 kHz mono buffer passed to whisper.
+    #[value(name = "16k")]
+    Whisper,
+    /// Both, the 16 kHz one with a `-16k` suffix.
+    Both,
+}
+
+/// Writes interleaved samples as a 32-bit float WAV file, creating parent
+/// directories as needed.
+pub fn write_wav(path: &Path, samples: &[f32], sample_rate: u32, channels: u16) -> Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let spec = hound::WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let write = || -> Result<()> {
+        let mut writer = hound::WavWriter::create(path, spec)?;
+        for &sample in samples {
+            writer.write_sample(sample)?;
+        }
+        writer.finalize()?;
+        Ok(())
+    };
+    write().with_context(|| format!("failed to save audio to {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_int_wav(path: &Path, bits: u16, samples: &[i32]) {
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 22_050,
+            bits_per_sample: bits,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for &sample in samples {
+            if bits == 8 {
+                writer.write_sample(sample as i8).unwrap();
+            } else {
+                writer.write_sample(sample).unwrap();
+            }
+        }
+        writer.finalize().unwrap();
+    }
+
+    #[test]
+    fn reads_int_wavs_at_full_scale() {
+        let dir = tempfile::tempdir().unwrap();
+        for bits in [8u16, 16, 24] {
+            let full = 1i32 << (bits - 1);
+            let path = dir.path().join(format!("{bits}.wav"));
+            write_int_wav(&path, bits, &[0, full / 2, -full, full / 4]);
+
+            let recording = read_audio_file(&path).unwrap();
+            assert_eq!(recording.sample_rate, 22_050, "{bits}-bit");
+            assert_eq!(recording.channels, 2, "{bits}-bit");
+            assert_eq!(recording.samples, [0.0, 0.5, -1.0, 0.25], "{bits}-bit");
+        }
+    }
+
+    #[test]
+    fn reads_float_wavs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("float.WAV");
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 48_000,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for sample in [0.25f32, -0.75, 1.0] {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let recording = read_audio_file(&path).unwrap();
+        assert_eq!((recording.sample_rate, recording.channels), (48_000, 1));
+        assert_eq!(recording.samples, [0.25, -0.75, 1.0]);
+    }
+
+    #[test]
+    fn write_wav_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("capture.wav");
+        let samples = [0.1f32, -0.2, 0.3, -0.4, 0.5, -0.6];
+        write_wav(&path, &samples, 44_100, 2).unwrap();
+
+        let recording = read_audio_file(&path).unwrap();
+        assert_eq!((recording.sample_rate, recording.channels), (44_100, 2));
+        assert_eq!(recording.samples, samples);
+    }
+
+    #[test]
+    fn rejects_empty_and_unreadable_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let empty = dir.path().join("empty.wav");
+        write_int_wav(&empty, 16, &[]);
+        assert!(read_audio_file(&empty).is_err());
+
+        let garbage = dir.path().join("garbage.wav");
+        std::fs::write(&garbage, b"not a wav file").unwrap();
+        assert!(read_audio_file(&garbage).is_err());
+        assert!(read_audio_file(&dir.path().join("missing.flac")).is_err());
+    }
+}
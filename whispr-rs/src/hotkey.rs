@@ -0,0 +1,414 @@
+use rdev::Key;
+use std::fmt;
+use std::str::FromStr;
+
+/// Names accepted for the non-modifier key of a chord; the first name of
+/// each key is used when printing.
+const KEY_NAMES: &[(&str, Key)] = &[
+    ("A", Key::KeyA), ("B", Key::KeyB), ("C", Key::KeyC), ("D", Key::KeyD),
+    ("E", Key::KeyE), ("F", Key::KeyF), ("G", Key::KeyG), ("H", Key::KeyH),
+    ("I", Key::KeyI), ("J", Key::KeyJ), ("K", Key::KeyK), ("L", Key::KeyL),
+    ("M", Key::KeyM), ("N", Key::KeyN), ("O", Key::KeyO), ("P", Key::KeyP),
+    ("Q", Key::KeyQ), ("R", Key::KeyR), ("S", Key::KeyS), ("T", Key::KeyT),
+    ("U", Key::KeyU), ("V", Key::KeyV), ("W", Key::KeyW), ("X", Key::KeyX),
+    ("Y", Key::KeyY), ("Z", Key::KeyZ),
+    ("0", Key::Num0), ("1", Key::Num1), ("2", Key::Num2), ("3", Key::Num3),
+    ("4", Key::Num4), ("5", Key::Num5), ("6", Key::Num6), ("7", Key::Num7),
+    ("8", Key::Num8), ("9", Key::Num9),
+    ("F1", Key::F1), ("F2", Key::F2), ("F3", Key::F3), ("F4", Key::F4),
+    ("F5", Key::F5), ("F6", Key::F6), ("F7", Key::F7), ("F8", Key::F8),
+    ("F9", Key::F9), ("F10", Key::F10), ("F11", Key::F11), ("F12", Key::F12),
+    ("Space", Key::Space),
+    ("Enter", Key::Return), ("Return", Key::Return),
+    ("Tab", Key::Tab),
+    ("Escape", Key::Escape), ("Esc", Key::Escape),
+    ("Backspace", Key::Backspace),
+    ("Insert", Key::Insert),
+    ("Delete", Key::Delete), ("Del", Key::Delete),
+    ("Home", Key::Home), ("End", Key::End),
+    ("PageUp", Key::PageUp), ("PageDown", Key::PageDown),
+    ("Up", Key::UpArrow), ("Down", Key::DownArrow),
+    ("Left", Key::LeftArrow), ("Right", Key::RightArrow),
+    ("PrintScreen", Key::PrintScreen), ("Pause", Key::Pause),
+    ("Minus", Key::Minus), ("-", Key::Minus),
+    ("Equal", Key::Equal), ("=", Key::Equal),
+    ("Comma", Key::Comma), (",", Key::Comma),
+    ("Period", Key::Dot), (".", Key::Dot),
+    ("Slash", Key::Slash), ("/", Key::Slash),
+    ("Semicolon", Key::SemiColon), (";", Key::SemiColon),
+    ("Quote", Key::Quote), ("'", Key::Quote),
+    ("Backquote", Key::BackQuote), ("`", Key::BackQuote),
+    ("LeftBracket", Key::LeftBracket), ("[", Key::LeftBracket),
+    ("RightBracket", Key::RightBracket), ("]", Key::RightBracket),
+    ("Backslash", Key::BackSlash), ("\\", Key::BackSlash),
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Modifiers {
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+    meta: bool,
+}
+
+impl Modifiers {
+    /// Updates the state for a modifier key; false for any other key.
+    fn set(&mut self, key: Key, down: bool) -> bool {
+        match key {
+            Key::ControlLeft | Key::ControlRight => self.ctrl = down,
+            Key::Alt | Key::AltGr => self.alt = down,
+            Key::ShiftLeft | Key::ShiftRight => self.shift = down,
+            Key::MetaLeft | Key::MetaRight => self.meta = down,
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// A key combination such as `Ctrl+Alt+Space`: any of Ctrl, Alt, Shift and
+/// Super (also `Meta`, `Cmd`, `Win`) followed by exactly one other key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Chord {
+    modifiers: Modifiers,
+    key: Key,
+}
+
+impl FromStr for Chord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = Modifiers::default();
+        let mut key = None;
+        for part in s.split('+').map(str::trim) {
+            match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => modifiers.ctrl = true,
+                "alt" | "option" => modifiers.alt = true,
+                "shift" => modifiers.shift = true,
+                "super" | "meta" | "cmd" | "win" => modifiers.meta = true,
+                "" => return Err(format!("invalid hotkey '{}': empty key name", s)),
+                name => {
+                    if key.is_some() {
+                        return Err(format!("invalid hotkey '{}': only one non-modifier key is allowed", s));
+                    }
+                    key = Some(
+                        KEY_NAMES
+                            .iter()
+                            .find(|(known, _)| known.eq_ignore_ascii_case(name))
+                            .map(|&(_, key)| key)
+                            .ok_or_else(|| format!("invalid hotkey '{}': unknown key '{}'", s, part))?,
+                    );
+                }
+            }
+        }
+        let key = key.ok_or_else(|| format!("invalid hotkey '{}': missing a non-modifier key", s))?;
+        Ok(Self { modifiers, key })
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Modifiers { ctrl, alt, shift, meta } = self.modifiers;
+        for (held, name) in [(ctrl, "Ctrl"), (alt, "Alt"), (shift, "Shift"), (meta, "Super")] {
+            if held {
+                write!(f, "{}+", name)?;
+            }
+        }
+        let name = KEY_NAMES
+            .iter()
+            .find(|(_, key)| *key == self.key)
+            .map_or("?", |(name, _)| name);
+        f.write_str(name)
+    }
+}
+
+/// What a hotkey does in listener mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Record, capture the screen and ask the AI backend.
+    Capture,
+    /// Print the text on screen.
+    OcrOnly,
+    /// Record and print the transcript.
+    AsrOnly,
+    /// Show the previous answer again.
+    RepeatLast,
+    /// Turn the overlay window on or off for the following captures.
+    ToggleOverlay,
+    /// Abort the capture in progress.
+    Cancel,
+    /// Forget the conversation history.
+    ResetConversation,
+}
+
+impl Action {
+    const ALL: [(&'static str, Action); 7] = [
+        ("capture", Action::Capture),
+        ("ocr-only", Action::OcrOnly),
+        ("asr-only", Action::AsrOnly),
+        ("repeat-last", Action::RepeatLast),
+        ("toggle-overlay", Action::ToggleOverlay),
+        ("cancel", Action::Cancel),
+        ("reset-conversation", Action::ResetConversation),
+    ];
+
+    fn name(self) -> &'static str {
+        Self::ALL
+            .iter()
+            .find(|(_, action)| *action == self)
+            .map_or("?", |(name, _)| name)
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(s.trim()))
+            .map(|&(_, action)| action)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|(name, _)| *name).collect();
+                format!("unknown hotkey action '{}': expected {}", s, names.join(", "))
+            })
+    }
+}
+
+/// A chord bound to an action, written `<chord>=<action>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Binding {
+    pub chord: Chord,
+    pub action: Action,
+}
+
+impl FromStr for Binding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (chord, action) = s
+            .rsplit_once('=')
+            .ok_or_else(|| format!("invalid binding '{}': expected <hotkey>=<action>, e.g. ctrl+alt+space=capture", s))?;
+        Ok(Self {
+            chord: chord.parse()?,
+            action: action.parse()?,
+        })
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:<16} {}", self.chord.to_string(), self.action.name())
+    }
+}
+
+const DEFAULT_BINDINGS: [(&str, Action); 7] = [
+    ("Ctrl+Alt+Space", Action::Capture),
+    ("Ctrl+Alt+O", Action::OcrOnly),
+    ("Ctrl+Alt+A", Action::AsrOnly),
+    ("Ctrl+Alt+L", Action::RepeatLast),
+    ("Ctrl+Alt+H", Action::ToggleOverlay),
+    ("Ctrl+Alt+X", Action::Cancel),
+    ("Ctrl+Alt+N", Action::ResetConversation),
+];
+
+/// The default bindings, minus those for actions `custom` rebinds, plus
+/// `custom`. Fails when two actions end up on the same chord, since only one
+/// of them could ever fire.
+pub fn bindings(custom: &[Binding]) -> Result<Vec<Binding>, String> {
+    let mut bindings = custom.to_vec();
+    for (chord, action) in DEFAULT_BINDINGS {
+        if !custom.iter().any(|binding| binding.action == action) {
+            bindings.push(Binding {
+                chord: chord.parse().expect("default hotkey is valid"),
+                action,
+            });
+        }
+    }
+
+    for (index, binding) in bindings.iter().enumerate() {
+        if let Some(other) = bindings[..index].iter().find(|other| other.chord == binding.chord) {
+            if other.action == binding.action {
+                continue;
+            }
+            let hint = if index >= custom.len() {
+                format!("; rebind {} with --bind <hotkey>={} to free it", binding.action.name(), binding.action.name())
+            } else {
+                String::new()
+            };
+            return Err(format!(
+                "{} is bound to both {} and {}{}",
+                binding.chord,
+                other.action.name(),
+                binding.action.name(),
+                hint
+            ));
+        }
+    }
+    Ok(bindings)
+}
+
+/// Follows key presses and releases and reports when a bound chord is hit.
+pub struct Hotkeys {
+    bindings: Vec<Binding>,
+    modifiers: Modifiers,
+    /// Non-modifier keys currently held, with the action they triggered.
+    held: Vec<(Key, Option<Action>)>,
+}
+
+impl Hotkeys {
+    pub fn new(bindings: Vec<Binding>) -> Self {
+        Self {
+            bindings,
+            modifiers: Modifiers::default(),
+            held: Vec::new(),
+        }
+    }
+
+    /// The action bound to the chord completed by pressing `key`, if any.
+    /// Modifiers must match exactly, so Ctrl+Alt+Space does not trigger a
+    /// Ctrl+Space binding.
+    pub fn press(&mut self, key: Key) -> Option<Action> {
+        // Held keys auto-repeat; only the first press counts.
+        if self.modifiers.set(key, true) || self.held.iter().any(|&(held, _)| held == key) {
+            return None;
+        }
+        let chord = Chord {
+            modifiers: self.modifiers,
+            key,
+        };
+        let action = self
+            .bindings
+            .iter()
+            .find(|binding| binding.chord == chord)
+            .map(|binding| binding.action);
+        self.held.push((key, action));
+        action
+    }
+
+    /// The action whose chord ends with `key` being released, for push-to-talk.
+    /// Letting go of a modifier first does not end the chord.
+    pub fn release(&mut self, key: Key) -> Option<Action> {
+        if self.modifiers.set(key, false) {
+            return None;
+        }
+        let index = self.held.iter().position(|&(held, _)| held == key)?;
+        self.held.remove(index).1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(s: &str) -> Binding {
+        s.parse().unwrap()
+    }
+
+    fn hotkeys(custom: &[&str]) -> Hotkeys {
+        let custom: Vec<Binding> = custom.iter().map(|s| binding(s)).collect();
+        Hotkeys::new(bindings(&custom).unwrap())
+    }
+
+    #[test]
+    fn parses_and_prints_chords() {
+        let chord: Chord = "ctrl+alt+space".parse().unwrap();
+        assert_eq!(chord.to_string(), "Ctrl+Alt+Space");
+        let chord: Chord = " Control + Option + Shift + Cmd + esc ".parse().unwrap();
+        assert_eq!(chord.to_string(), "Ctrl+Alt+Shift+Super+Escape");
+        assert_eq!("f12".parse::<Chord>().unwrap().to_string(), "F12");
+        assert_eq!("alt+/".parse::<Chord>().unwrap().to_string(), "Alt+Slash");
+    }
+
+    #[test]
+    fn rejects_bad_chords() {
+        for chord in ["", "ctrl+", "ctrl+alt", "ctrl+a+b", "ctrl+nope", "ctrl++a"] {
+            assert!(chord.parse::<Chord>().is_err(), "{chord:?} should fail");
+        }
+    }
+
+    #[test]
+    fn parses_bindings() {
+        let parsed = binding("ctrl+alt+o=Capture");
+        assert_eq!(parsed.chord, "Ctrl+Alt+O".parse().unwrap());
+        assert_eq!(parsed.action, Action::Capture);
+        // The last '=' separates the action, so '=' itself can be bound.
+        assert_eq!(binding("ctrl+==ocr-only").chord.to_string(), "Ctrl+Equal");
+
+        for bad in ["ctrl+a", "ctrl+a=fly", "=capture"] {
+            assert!(bad.parse::<Binding>().is_err(), "{bad:?} should fail");
+        }
+    }
+
+    #[test]
+    fn custom_bindings_replace_defaults() {
+        let defaults = bindings(&[]).unwrap();
+        assert_eq!(defaults.len(), DEFAULT_BINDINGS.len());
+
+        let custom = [binding("f9=capture"), binding("f10=capture")];
+        let merged = bindings(&custom).unwrap();
+        let capture: Vec<String> = merged
+            .iter()
+            .filter(|b| b.action == Action::Capture)
+            .map(|b| b.chord.to_string())
+            .collect();
+        assert_eq!(capture, ["F9", "F10"]);
+        assert_eq!(merged.len(), DEFAULT_BINDINGS.len() + 1);
+    }
+
+    #[test]
+    fn rejects_duplicate_chords() {
+        let err = bindings(&[binding("ctrl+alt+o=capture")]).unwrap_err();
+        assert!(err.contains("Ctrl+Alt+O") && err.contains("ocr-only"), "{err}");
+        assert!(bindings(&[binding("f9=capture"), binding("f9=cancel")]).is_err());
+        // Moving the default out of the way first is fine.
+        assert!(bindings(&[binding("ctrl+alt+o=capture"), binding("f9=ocr-only")]).is_ok());
+    }
+
+    #[test]
+    fn modifiers_must_match_exactly() {
+        let mut keys = hotkeys(&["ctrl+space=asr-only"]);
+        keys.press(Key::ControlLeft);
+        keys.press(Key::Alt);
+        assert_eq!(keys.press(Key::Space), Some(Action::Capture));
+        keys.release(Key::Space);
+        keys.release(Key::Alt);
+        assert_eq!(keys.press(Key::Space), Some(Action::AsrOnly));
+        keys.release(Key::Space);
+        keys.release(Key::ControlLeft);
+        assert_eq!(keys.press(Key::Space), None);
+    }
+
+    #[test]
+    fn auto_repeat_fires_once() {
+        let mut keys = hotkeys(&[]);
+        keys.press(Key::ControlRight);
+        keys.press(Key::AltGr);
+        assert_eq!(keys.press(Key::KeyO), Some(Action::OcrOnly));
+        assert_eq!(keys.press(Key::KeyO), None);
+        assert_eq!(keys.press(Key::KeyO), None);
+        assert_eq!(keys.release(Key::KeyO), Some(Action::OcrOnly));
+        assert_eq!(keys.press(Key::KeyO), Some(Action::OcrOnly));
+    }
+
+    #[test]
+    fn release_ends_the_chord_even_after_modifiers() {
+        let mut keys = hotkeys(&[]);
+        keys.press(Key::ControlLeft);
+        keys.press(Key::Alt);
+        assert_eq!(keys.press(Key::Space), Some(Action::Capture));
+        // Letting go of the modifiers first must not end push-to-talk early...
+        assert_eq!(keys.release(Key::ControlLeft), None);
+        assert_eq!(keys.release(Key::Alt), None);
+        // ...and releasing the key still reports the action it started.
+        assert_eq!(keys.release(Key::Space), Some(Action::Capture));
+        assert_eq!(keys.release(Key::Space), None);
+    }
+
+    #[test]
+    fn unbound_keys_are_ignored() {
+        let mut keys = hotkeys(&[]);
+        assert_eq!(keys.press(Key::KeyA), None);
+        assert_eq!(keys.release(Key::KeyA), None);
+        assert_eq!(keys.release(Key::KeyB), None);
+    }
+}
@@ -1,15 +1,32 @@
 mod audio;
+mod decode;
+mod config;
 mod ocr;
+mod capture;
+mod window;
+mod preprocess;
 mod ai;
 mod ui;
 mod blockchain;
 mod utils;
+mod stream;
+mod vad;
+mod prompt;
+mod resample;
+mod hotkey;
+mod redact;
 
-use anyhow::{Context, Result};
-use clap::Parser;
+use anyhow::{bail, Context, Result};
+use clap::{CommandFactory, FromArgMatches, Parser};
 use colored::*;
 use indicatif::{ProgressBar, ProgressStyle};
-use rdev::{listen, Event, EventType, Key};
+use rdev::{listen, Event, EventType};
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use image::RgbaImage;
+use std::sync::mpsc::{self, Sender};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
@@ -19,27 +36,46 @@ fn load_env() {
     dotenv::dotenv();
 }
 
-use audio::{record_audio, downmix_to_mono_f32, linear_resample, transcribe_audio};
-use ocr::capture_and_ocr;
-use ai::{generate_response, get_api_key_from_env_or_arg};
-use ui::show_overlay;
+use audio::{describe_input_devices, record_audio, record_with_vad, PushToTalkRecorder, InputOptions, transcribe_audio, AudioRecording, Transcriber, WHISPER_SAMPLE_RATE};
+use decode::{read_audio_file, write_wav, SavedAudio};
+use ocr::{capture_and_ocr, save_screenshot, OcrOptions, ScreenCapture};
+use window::ActiveWindow;
+use preprocess::Preprocess;
+use capture::{describe_displays, CaptureOptions, DisplaySelection, Region};
+use ai::{create_backend, encode_screenshot, generate_response, generate_response_stream, BackendKind, ResponseBackend, BackendOptions, Conversation, EncodedImage, GenerationConfig, ImageEncoding, SafetyThreshold, ScreenContext, UploadFormat};
+use ui::{run_overlay, show_overlay, show_overlay_streaming, Overlay, OverlayUpdate};
 use blockchain::log_to_solana;
 use utils::truncate;
+use stream::{stream_transcription, StreamEvent, StreamOptions};
+use vad::VadOptions;
+use prompt::{PromptContext, PromptTemplate};
+use config::{settings_to_args, Config};
+use resample::{resample, ResampleQuality};
+use hotkey::{Action, Binding, Hotkeys};
+use redact::Redactor;
 
 #[derive(Parser, Debug, Clone)]
 #[command(name = "whispr-rs")]
 #[command(about = "Whispr - Discrete AI assistant that listens when you can't", long_about = None)]
 struct Args {
+    /// Config file (default: config.toml in the Whispr config dir)
+    #[arg(long = "config", env = "WHISPR_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Named profile from the config file to apply
+    #[arg(long = "profile", env = "WHISPR_PROFILE")]
+    profile: Option<String>,
+
     /// Duration in seconds to record audio (default: 5)
     #[arg(short = 'd', long = "duration", default_value_t = 5)]
     duration_secs: u64,
 
     /// Path to whisper model (default: ./models/ggml-base.en.bin)
-    #[arg(short = 'm', long = "model", default_value = "./models/ggml-base.en.bin")]
+    #[arg(short = 'm', long = "model", env = "WHISPR_MODEL", default_value = "./models/ggml-base.en.bin")]
     model: String,
 
     /// Language code for transcription (default: en)
-    #[arg(short = 'l', long = "language", default_value = "en")]
+    #[arg(short = 'l', long = "language", env = "WHISPR_LANGUAGE", default_value = "en")]
     language: String,
 
     /// Run OCR only (screenshot + OCR, no ASR)
@@ -54,10 +90,66 @@ struct Args {
     #[arg(long = "tesseract")]
     tesseract: Option<String>,
 
+    /// Drop OCR words with a Tesseract confidence (0-100) below this
+    #[arg(long = "min-confidence", default_value_t = 30.0)]
+    min_confidence: f32,
+
+    /// OCR preprocessing steps: grayscale, invert (dark themes), upscale[=N], deskew, threshold, all or none
+    #[arg(long = "preprocess", default_value = "grayscale,invert")]
+    preprocess: Preprocess,
+
+    /// Language model backend used for responses
+    #[arg(long = "backend", env = "WHISPR_BACKEND", value_enum, default_value_t = BackendKind::Gemini)]
+    backend: BackendKind,
+
     /// Gemini API key (or set GEMINI_API_KEY env var)
-    #[arg(long = "gemini-key")]
+    #[arg(long = "gemini-key", env = "GEMINI_API_KEY", hide_env_values = true)]
     gemini_key: Option<String>,
 
+    /// Gemini model name
+    #[arg(long = "gemini-model", default_value = "gemini-2.0-flash-exp")]
+    gemini_model: String,
+
+    /// Base URL of the Gemini API (point at a mock server for testing)
+    #[arg(long = "gemini-url", default_value = "https://generativelanguage.googleapis.com/v1beta")]
+    gemini_url: String,
+
+    /// Gemini safety threshold applied to all harm categories
+    #[arg(long = "gemini-safety", value_enum)]
+    gemini_safety: Option<SafetyThreshold>,
+
+    /// Sampling temperature for the response model
+    #[arg(long = "temperature")]
+    temperature: Option<f32>,
+
+    /// Nucleus sampling probability mass for the response model
+    #[arg(long = "top-p")]
+    top_p: Option<f32>,
+
+    /// Maximum number of tokens in the response
+    #[arg(long = "max-tokens")]
+    max_tokens: Option<u32>,
+
+    /// API key for the OpenAI-compatible backend (or set OPENAI_API_KEY env var)
+    #[arg(long = "openai-key", env = "OPENAI_API_KEY", hide_env_values = true)]
+    openai_key: Option<String>,
+
+    /// Base URL of the OpenAI-compatible API
+    #[arg(long = "openai-url", default_value = "https://api.openai.com/v1")]
+    openai_url: String,
+
+    /// Model name for the OpenAI-compatible backend
+    #[arg(long = "openai-model", default_value = "gpt-4o-mini")]
+    openai_model: String,
+
+    /// Base URL of the Ollama server
+    #[arg(long = "ollama-url", default_value = "http://localhost:11434")]
+    ollama_url: String,
+
+    /// Ollama model name (must support images to use the screenshot)
+    #[arg(long = "ollama-model", default_value = "llava")]
+    ollama_model: String,
+
     /// Log summary to Solana devnet
     #[arg(long = "solana-log", default_value_t = false)]
     solana_log: bool,
@@ -70,13 +162,152 @@ struct Args {
     #[arg(long = "no-chain", default_value_t = false)]
     no_chain: bool,
 
-    /// Listen for global hotkey (Ctrl+Shift+W) to trigger demo
+    /// Run in the background and react to global hotkeys (see --bind)
     #[arg(long = "listen", default_value_t = false)]
     listen: bool,
 
+    /// In listener mode, record while the capture or asr-only hotkey is held
+    /// (up to --max-duration) instead of for --duration
+    #[arg(long = "push-to-talk", default_value_t = false)]
+    push_to_talk: bool,
+
+    /// Bind a hotkey in listener mode: <hotkey>=<action>, e.g. ctrl+alt+space=capture.
+    /// Actions: capture, ocr-only, asr-only, repeat-last, toggle-overlay, cancel,
+    /// reset-conversation. Replaces the default hotkey of that action (repeatable)
+    #[arg(long = "bind")]
+    bind: Vec<Binding>,
+
     /// Show overlay window with results
     #[arg(long = "overlay", default_value_t = false)]
     overlay: bool,
+
+    /// Number of previous exchanges sent as context in listener mode (0 disables memory)
+    #[arg(long = "history", default_value_t = 5)]
+    history: usize,
+
+    /// Approximate token budget for the conversation history
+    #[arg(long = "history-tokens", default_value_t = 8000)]
+    history_tokens: usize,
+
+    /// Prompt preset: default, code-reviewer, note-taker, translator, or a
+    /// custom <name>.txt in the config dir's prompts/ folder
+    #[arg(long = "persona", default_value = "default")]
+    persona: String,
+
+    /// Prompt template file (overrides --persona)
+    #[arg(long = "prompt")]
+    prompt: Option<PathBuf>,
+
+    /// Display to capture: index, id:<n>, name:<output> (X11), primary, cursor, all or window (focused window only)
+    #[arg(long = "display", default_value = "primary")]
+    display: DisplaySelection,
+
+    /// Capture only this rectangle of the selected display(s): x,y,width,height
+    #[arg(long = "region")]
+    region: Option<Region>,
+
+    /// List available displays and exit
+    #[arg(long = "list-displays", default_value_t = false)]
+    list_displays: bool,
+
+    /// Also write each screenshot to this file (screenshots are otherwise kept in memory only)
+    #[arg(long = "save-screenshot")]
+    save_screenshot: Option<PathBuf>,
+
+    /// Image format for the screenshot sent to the AI backend
+    #[arg(long = "image-format", value_enum, default_value_t = UploadFormat::Png)]
+    image_format: UploadFormat,
+
+    /// Scale screenshots down so the longest side is at most this many pixels before upload
+    #[arg(long = "image-max-dim")]
+    image_max_dim: Option<u32>,
+
+    /// JPEG/WebP quality (1-100)
+    #[arg(long = "image-quality", default_value_t = 80, value_parser = clap::value_parser!(u8).range(1..=100))]
+    image_quality: u8,
+
+    /// Send only the prompt and OCR text, without the screenshot (for text-only models)
+    #[arg(long = "text-only", default_value_t = false)]
+    text_only: bool,
+
+    /// Send transcripts, screen text and screenshots without masking emails, keys, card numbers and IBANs
+    #[arg(long = "no-redact", default_value_t = false)]
+    no_redact: bool,
+
+    /// Extra regex whose matches are redacted before upload (repeatable)
+    #[arg(long = "redact-pattern")]
+    redact_patterns: Vec<String>,
+
+    /// Print the AI response token by token as it is generated
+    #[arg(long = "stream-response", default_value_t = false)]
+    stream_response: bool,
+
+    /// Stream live transcription from the microphone until Ctrl+C
+    #[arg(long = "stream", default_value_t = false)]
+    stream: bool,
+
+    /// Milliseconds of new audio collected between transcription passes in stream mode
+    #[arg(long = "stream-step", default_value_t = 2000)]
+    stream_step_ms: u64,
+
+    /// Maximum window length in milliseconds before stream text is finalized
+    #[arg(long = "stream-window", default_value_t = 10_000)]
+    stream_window_ms: u64,
+
+    /// Transcribe this audio file instead of recording (WAV; FLAC/MP3/OGG with the
+    /// `audio-formats` feature)
+    #[arg(long = "input")]
+    input: Option<PathBuf>,
+
+    /// Microphone to record from: index or (part of) the name from --list-devices
+    #[arg(long = "device")]
+    device: Option<String>,
+
+    /// Recording sample rate in Hz (default: the device's own)
+    #[arg(long = "sample-rate")]
+    sample_rate: Option<u32>,
+
+    /// Number of recording channels (default: the device's own)
+    #[arg(long = "channels")]
+    channels: Option<u16>,
+
+    /// List audio hosts, input devices and their supported formats, then exit
+    #[arg(long = "list-devices", default_value_t = false)]
+    list_devices: bool,
+
+    /// Write captured audio to this WAV file, or to a new timestamped file per
+    /// capture if it is a directory (ends with '/' or already exists). Not
+    /// available with --stream
+    #[arg(long = "save-audio")]
+    save_audio: Option<PathBuf>,
+
+    /// Which audio --save-audio writes: the raw capture, the 16 kHz whisper input, or both
+    #[arg(long = "save-audio-kind", value_enum, default_value_t = SavedAudio::Both)]
+    save_audio_kind: SavedAudio,
+
+    /// Resampler used to convert microphone audio to 16 kHz for whisper
+    #[arg(long = "resample-quality", value_enum, default_value_t = ResampleQuality::Balanced)]
+    resample_quality: ResampleQuality,
+
+    /// Start recording when speech is detected and stop on silence (replaces --duration)
+    #[arg(long = "vad", default_value_t = false)]
+    vad: bool,
+
+    /// RMS level (0.0-1.0) a frame needs to count as speech
+    #[arg(long = "vad-threshold", default_value_t = 0.01)]
+    vad_threshold: f32,
+
+    /// Milliseconds of silence that end a VAD recording
+    #[arg(long = "silence-timeout", default_value_t = 1000)]
+    silence_timeout_ms: u64,
+
+    /// Hard limit in seconds for a VAD recording
+    #[arg(long = "max-duration", default_value_t = 30)]
+    max_duration_secs: u64,
+
+    /// Seconds to wait for speech to start before giving up
+    #[arg(long = "vad-start-timeout", default_value_t = 10)]
+    vad_start_timeout_secs: u64,
 }
 
 fn main() -> Result<()> {
@@ -84,22 +315,45 @@ fn main() -> Result<()> {
     load_env();
     
     env_logger::init();
-    let args = Args::parse();
+    let args = load_args()?;
+
+    if args.list_displays {
+        for display in describe_displays()? {
+            println!("{}", display);
+        }
+        return Ok(());
+    }
+
+    if args.list_devices {
+        for device in describe_input_devices()? {
+            println!("{}", device);
+        }
+        return Ok(());
+    }
 
     if args.listen {
         return run_hotkey_listener(args);
     }
 
     if args.demo {
-        return run_demo_mode(&args);
+        let mut session = Session::new(&args)?;
+        return run_demo_mode(&args, &mut session);
     }
 
     if args.combined {
         return run_combined_mode(&args);
     }
 
+    if args.stream {
+        return run_stream_mode(&args);
+    }
+
     if args.ocr {
-        let (text, _img_path) = capture_and_ocr(&args.language, args.tesseract.as_deref())?;
+        let capture = capture_and_ocr(&ocr_options(&args), &capture_options(&args, None))?;
+        if let Some(path) = &args.save_screenshot {
+            save_screenshot(&capture.image, path)?;
+        }
+        let text = capture.text;
         if text.trim().is_empty() {
             println!("(no text detected)");
         } else {
@@ -109,22 +363,16 @@ fn main() -> Result<()> {
     }
 
     // Default mode: ASR only
-    println!("Recording {}s of audio...", args.duration_secs);
-    let recorded = record_audio(Duration::from_secs(args.duration_secs))
-        .context("failed to record audio")?;
-
-    let mono_f32 = downmix_to_mono_f32(&recorded.data, recorded.channels, recorded.sample_format);
+    println!("{}", recording_message(&args));
+    let recorded = capture_audio(&args, &AtomicBool::new(false))?;
 
-    let target_sr = 16_000u32;
-    let audio_16k = if recorded.sample_rate != target_sr {
+    if recorded.sample_rate != WHISPER_SAMPLE_RATE {
         println!(
             "Resampling from {} Hz to {} Hz...",
-            recorded.sample_rate, target_sr
+            recorded.sample_rate, WHISPER_SAMPLE_RATE
         );
-        linear_resample(&mono_f32, recorded.sample_rate, target_sr)
-    } else {
-        mono_f32
-    };
+    }
+    let audio_16k = to_whisper_input(&args, &recorded)?;
 
     println!("Transcribing...");
     let asr_text = transcribe_audio(&args.model, &audio_16k, &args.language)?;
@@ -138,7 +386,262 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn run_demo_mode(args: &Args) -> Result<()> {
+/// Parses the command line and fills in whatever it leaves unset from the
+/// config file. Precedence: command line, environment, profile, top-level
+/// config settings, built-in defaults.
+fn load_args() -> Result<Args> {
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    let Some(config) = Config::load(args.config.as_deref())? else {
+        if let Some(profile) = &args.profile {
+            bail!("--profile {} needs a config file, but none was found", profile);
+        }
+        return Ok(args);
+    };
+    let settings = config.settings(args.profile.as_deref())?;
+
+    let mut argv = vec![OsString::from("whispr-rs")];
+    argv.extend(
+        settings_to_args(&Args::command(), &matches, &settings)
+            .with_context(|| format!("invalid config file {}", config.path.display()))?,
+    );
+    argv.extend(std::env::args_os().skip(1));
+    Args::try_parse_from(argv).with_context(|| format!("invalid setting in config file {}", config.path.display()))
+}
+
+/// Reads the `--input` file, or records from the microphone, either for the
+/// fixed `--duration` or, with `--vad`, for as long as someone is speaking.
+/// Recording ends early once `cancel` is set.
+fn capture_audio(args: &Args, cancel: &AtomicBool) -> Result<AudioRecording> {
+    if let Some(path) = &args.input {
+        return read_audio_file(path);
+    }
+    if args.vad {
+        let options = VadOptions {
+            threshold: args.vad_threshold,
+            silence_timeout: Duration::from_millis(args.silence_timeout_ms),
+            max_duration: Duration::from_secs(args.max_duration_secs),
+            start_timeout: Duration::from_secs(args.vad_start_timeout_secs),
+            ..VadOptions::default()
+        };
+        record_with_vad(&input_options(args), &options, cancel).context("failed to record audio")
+    } else {
+        record_audio(&input_options(args), Duration::from_secs(args.duration_secs), cancel)
+            .context("failed to record audio")
+    }
+}
+
+fn input_options(args: &Args) -> InputOptions {
+    InputOptions {
+        device: args.device.clone(),
+        sample_rate: args.sample_rate,
+        channels: args.channels,
+    }
+}
+
+fn recording_message(args: &Args) -> String {
+    if let Some(path) = &args.input {
+        format!("Reading {}...", path.display())
+    } else if push_to_talk(args) {
+        format!("Recording while the hotkey is held (max {}s)...", args.max_duration_secs)
+    } else if args.vad {
+        format!(
+            "Listening for speech (stops after {}ms of silence, max {}s)...",
+            args.silence_timeout_ms, args.max_duration_secs
+        )
+    } else {
+        format!("Recording {}s of audio...", args.duration_secs)
+    }
+}
+
+/// Push-to-talk only applies to hotkeys, so it is ignored outside listener
+/// mode and when reading from a file.
+fn push_to_talk(args: &Args) -> bool {
+    args.listen && args.push_to_talk && args.input.is_none()
+}
+
+/// Converts a recording into the 16 kHz mono buffer whisper expects,
+/// writing both out first if `--save-audio` is set.
+fn to_whisper_input(args: &Args, recorded: &AudioRecording) -> Result<Vec<f32>> {
+    let audio_16k = resample(&recorded.to_mono(), recorded.sample_rate, WHISPER_SAMPLE_RATE, args.resample_quality);
+    if let Some(path) = &args.save_audio {
+        save_audio(path, args.save_audio_kind, recorded, &audio_16k)?;
+    }
+    Ok(audio_16k)
+}
+
+fn save_audio(path: &Path, kind: SavedAudio, recorded: &AudioRecording, audio_16k: &[f32]) -> Result<()> {
+    let is_dir = path.is_dir() || path.as_os_str().to_string_lossy().ends_with(std::path::is_separator);
+    let (raw_path, path_16k) = if is_dir {
+        // Milliseconds plus a counter, so quick listener captures never overwrite each other.
+        let time = chrono::Local::now().format("%Y%m%d-%H%M%S-%3f");
+        let mut stem = format!("whispr-{}", time);
+        let mut n = 1;
+        while path.join(format!("{}.wav", stem)).exists() || path.join(format!("{}-16k.wav", stem)).exists() {
+            n += 1;
+            stem = format!("whispr-{}-{}", time, n);
+        }
+        (path.join(format!("{}.wav", stem)), path.join(format!("{}-16k.wav", stem)))
+    } else if kind == SavedAudio::Whisper {
+        (path.to_path_buf(), path.to_path_buf())
+    } else {
+        let stem = path.file_stem().map_or_else(|| "audio".into(), |stem| stem.to_string_lossy());
+        (path.to_path_buf(), path.with_file_name(format!("{}-16k.wav", stem)))
+    };
+
+    if kind != SavedAudio::Whisper {
+        write_wav(&raw_path, &recorded.samples, recorded.sample_rate, recorded.channels)?;
+        println!("Saved audio to {}", raw_path.display());
+    }
+    if kind != SavedAudio::Raw {
+        write_wav(&path_16k, audio_16k, WHISPER_SAMPLE_RATE, 1)?;
+        println!("Saved audio to {}", path_16k.display());
+    }
+    Ok(())
+}
+
+fn backend_options(args: &Args) -> BackendOptions {
+    BackendOptions {
+        generation: GenerationConfig {
+            temperature: args.temperature,
+            top_p: args.top_p,
+            max_tokens: args.max_tokens,
+        },
+        gemini_key: args.gemini_key.clone(),
+        gemini_model: args.gemini_model.clone(),
+        gemini_url: args.gemini_url.clone(),
+        gemini_safety: args.gemini_safety,
+        openai_key: args.openai_key.clone(),
+        openai_url: args.openai_url.clone(),
+        openai_model: args.openai_model.clone(),
+        ollama_url: args.ollama_url.clone(),
+        ollama_model: args.ollama_model.clone(),
+    }
+}
+
+fn run_stream_mode(args: &Args) -> Result<()> {
+    if args.save_audio.is_some() {
+        bail!("--save-audio is not supported in --stream mode");
+    }
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_handler = stop.clone();
+    ctrlc::set_handler(move || stop_handler.store(true, Ordering::Relaxed))
+        .context("failed to install Ctrl+C handler")?;
+
+    let options = StreamOptions {
+        step: Duration::from_millis(args.stream_step_ms),
+        window: Duration::from_millis(args.stream_window_ms),
+        resample_quality: args.resample_quality,
+        input: input_options(args),
+        ..StreamOptions::default()
+    };
+
+    let mut transcriber = Transcriber::new(&args.model)?;
+
+    println!("{}", "Streaming transcription... press Ctrl+C to stop".bright_green());
+    stream_transcription(&mut transcriber, &args.language, &options, &stop, |event| {
+        match event {
+            StreamEvent::Partial(text) => {
+                print!("\r\x1b[2K{}", text.dimmed());
+                let _ = std::io::stdout().flush();
+            }
+            StreamEvent::Final(text) => println!("\r\x1b[2K{}", text),
+        }
+    })?;
+    println!();
+
+    Ok(())
+}
+
+fn ocr_options(args: &Args) -> OcrOptions<'_> {
+    OcrOptions {
+        language: &args.language,
+        tesseract_cli: args.tesseract.as_deref(),
+        min_confidence: args.min_confidence,
+        preprocess: args.preprocess,
+    }
+}
+
+/// Screen capture settings. `cursor` is only known in listener mode, where
+/// mouse moves are observed; elsewhere `--display cursor` uses the primary display.
+fn capture_options(args: &Args, cursor: Option<(i32, i32)>) -> CaptureOptions {
+    if args.display == DisplaySelection::Cursor && cursor.is_none() {
+        eprintln!("{}", "Cursor position unknown, capturing the primary display".dimmed());
+    }
+    CaptureOptions {
+        display: args.display.clone(),
+        region: args.region,
+        cursor,
+    }
+}
+
+/// State that outlives a single capture: the loaded whisper model and, in
+/// listener mode, the conversation so far and the last mouse position.
+struct Session {
+    transcriber: Transcriber,
+    conversation: Conversation,
+    redactor: Redactor,
+    cursor: Option<(i32, i32)>,
+    /// Set from the hotkey thread to abort the capture in progress.
+    cancel: Arc<AtomicBool>,
+    /// True while a push-to-talk hotkey is held down.
+    talking: Arc<AtomicBool>,
+    /// Open for the whole listener session with `--push-to-talk`.
+    recorder: Option<PushToTalkRecorder>,
+    /// The listener's long-lived overlay; elsewhere each answer opens its own.
+    overlay: Option<Overlay>,
+    last_answer: Option<LastAnswer>,
+}
+
+struct LastAnswer {
+    asr_text: String,
+    ocr_text: String,
+    response: String,
+}
+
+impl Session {
+    fn new(args: &Args) -> Result<Self> {
+        Ok(Self {
+            transcriber: Transcriber::new(&args.model)?,
+            conversation: Conversation::new(args.history, args.history_tokens),
+            redactor: redactor(args)?,
+            cursor: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+            talking: Arc::new(AtomicBool::new(false)),
+            recorder: None,
+            overlay: None,
+            last_answer: None,
+        })
+    }
+
+    /// Records until the hotkey is released with `--push-to-talk`, otherwise
+    /// like the other modes.
+    fn record(&self, args: &Args) -> Result<AudioRecording> {
+        if !push_to_talk(args) {
+            return capture_audio(args, &self.cancel);
+        }
+        let Some(recorder) = &self.recorder else {
+            bail!("push-to-talk recorder is not running");
+        };
+        recorder
+            .take(|| self.cancel.load(Ordering::SeqCst), Duration::from_secs(args.max_duration_secs))
+            .context("failed to record audio")
+    }
+
+    /// Fails once the cancel hotkey was pressed, so a capture stops at the next step.
+    fn check_cancelled(&self) -> Result<()> {
+        if self.cancel.load(Ordering::SeqCst) {
+            bail!("cancelled");
+        }
+        Ok(())
+    }
+}
+
+fn run_demo_mode(args: &Args, session: &mut Session) -> Result<()> {
+    // Loaded per capture so edits to a template file apply on the next hotkey press.
+    let template = PromptTemplate::load(args.prompt.as_deref(), &args.persona)?;
+
     println!();
     println!("{}", "╔═══════════════════════════════════════════════════════════════╗".bright_cyan().bold());
     println!("{}", "║          🎤 WHISPR - AI Assistant Demo Mode                ║".bright_cyan().bold());
@@ -146,29 +649,18 @@ fn run_demo_mode(args: &Args) -> Result<()> {
     println!("{}", "╚═══════════════════════════════════════════════════════════════╝".bright_cyan().bold());
     println!();
 
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_style(
-        ProgressStyle::default_spinner()
-            .template("{spinner:.cyan} {msg}")
-            .unwrap()
-    );
+    let spinner = demo_spinner();
 
     // Step 1: ASR
     println!("{}", "┌─ Step 1: Audio Input & Transcription (ASR)".bright_yellow().bold());
-    spinner.set_message("Recording audio...");
+    spinner.set_message(recording_message(args));
     spinner.enable_steady_tick(Duration::from_millis(100));
 
-    let recorded = record_audio(Duration::from_secs(args.duration_secs))
-        .context("failed to record audio")?;
+    let recorded = session.record(args)?;
+    session.check_cancelled()?;
     
     spinner.set_message("Processing audio...");
-    let mono_f32 = downmix_to_mono_f32(&recorded.data, recorded.channels, recorded.sample_format);
-    let target_sr = 16_000u32;
-    let audio_16k = if recorded.sample_rate != target_sr {
-        linear_resample(&mono_f32, recorded.sample_rate, target_sr)
-    } else {
-        mono_f32
-    };
+    let audio_16k = to_whisper_input(args, &recorded)?;
 
     spinner.finish_and_clear();
     println!("  {} Audio captured", "✓".green().bold());
@@ -176,9 +668,10 @@ fn run_demo_mode(args: &Args) -> Result<()> {
     spinner.set_message("Transcribing speech...");
     spinner.enable_steady_tick(Duration::from_millis(100));
 
-    let asr_text = transcribe_audio(&args.model, &audio_16k, &args.language)?;
+    let (asr_text, asr_redactions) = session.redactor.redact_text(&session.transcriber.transcribe(&audio_16k, &args.language)?);
 
     spinner.finish_and_clear();
+    session.check_cancelled()?;
     println!("  {} Transcription complete", "✓".green().bold());
     println!("  {} {}", "→".bright_blue(), if asr_text.trim().is_empty() { 
         "(no speech detected)".dimmed().to_string() 
@@ -192,36 +685,240 @@ fn run_demo_mode(args: &Args) -> Result<()> {
     spinner.set_message("Capturing screenshot...");
     spinner.enable_steady_tick(Duration::from_millis(100));
 
-    let (ocr_text, screenshot_path) = capture_and_ocr(&args.language, args.tesseract.as_deref())?;
+    let capture = capture_options(args, session.cursor);
+    let mut capture = capture_and_ocr(&ocr_options(args), &capture)?;
+    let redactions = asr_redactions + session.redactor.redact_capture(&mut capture);
+    let ocr = capture.confident_ocr();
+    let ScreenCapture { text: ocr_text, image: screenshot, window, .. } = capture;
+    if let Some(path) = &args.save_screenshot {
+        save_screenshot(&screenshot, path)?;
+    }
 
     spinner.finish_and_clear();
     println!("  {} Screenshot captured & processed", "✓".green().bold());
+    if redactions > 0 {
+        println!("  {} {}", "🔒".bright_blue(), describe_redactions(redactions).dimmed());
+    }
+    if let Some(window) = &window {
+        println!("  {} {} ({})", "🪟".bright_blue(), window.title.bright_white(), window.app_name);
+    }
     let preview = if ocr_text.trim().is_empty() { 
         "(no text detected)".dimmed().to_string()
     } else {
-        let truncated = truncate(&ocr_text, 80);
-        format!("\"{}\"", truncated.bright_white())
+        let truncated = truncate(&ocr.plain_text(), 80);
+        let confidence = ocr.mean_confidence().unwrap_or(0.0);
+        format!("\"{}\" {}", truncated.bright_white(), format!("({:.0}% confidence)", confidence).dimmed())
     };
     println!("  {} {}", "→".bright_blue(), preview);
+    let upload = encode_upload(args, &screenshot)?;
+    if let Some(image) = &upload {
+        println!("  {} {}", "📤".bright_blue(), describe_upload(image).dimmed());
+    }
     println!();
+    session.check_cancelled()?;
+
+    let backend: Arc<dyn ResponseBackend> = create_backend(args.backend, &backend_options(args))?.into();
+    let context = prompt_context(&asr_text, &ocr_text, window.as_ref(), &args.language, upload.is_some());
+    let prompt = template.render(&context)?;
+
+    // Without the listener's overlay a streaming overlay has to own the main
+    // thread, so the response and Solana steps move to a worker that feeds it tokens.
+    if args.overlay && args.stream_response && session.overlay.is_none() {
+        let (tx, rx) = mpsc::channel();
+        let worker_args = args.clone();
+        // The overlay event loop ends the process when closed, so the worker's
+        // copy of the conversation never needs to be merged back.
+        let mut worker_conversation = session.conversation.clone();
+        let worker_ocr_text = ocr_text.clone();
+        let worker_cancel = session.cancel.clone();
+        std::thread::spawn(move || {
+            let result = run_demo_response_steps(
+                &worker_args,
+                backend,
+                prompt,
+                &screen_context(&worker_ocr_text, upload.as_ref()),
+                &mut worker_conversation,
+                &worker_cancel,
+                Some(&tx),
+            );
+            let update = match result {
+                Ok(_) => OverlayUpdate::Done,
+                Err(e) => {
+                    eprintln!("{} {}", "Error:".red().bold(), e);
+                    OverlayUpdate::Error(e.to_string())
+                }
+            };
+            let _ = tx.send(update);
+        });
+        return show_overlay_streaming(&asr_text, &ocr_text, rx);
+    }
+
+    let updates = match &session.overlay {
+        Some(overlay) if args.overlay && args.stream_response => Some(overlay.start(&asr_text)),
+        _ => None,
+    };
+    let result = run_demo_response_steps(
+        args,
+        backend,
+        prompt,
+        &screen_context(&ocr_text, upload.as_ref()),
+        &mut session.conversation,
+        &session.cancel,
+        updates.as_ref(),
+    );
+    if let Some(tx) = &updates {
+        let _ = tx.send(match &result {
+            Ok(_) => OverlayUpdate::Done,
+            Err(e) => OverlayUpdate::Error(e.to_string()),
+        });
+    }
+    let response = result?;
+
+    let last = session.last_answer.insert(LastAnswer {
+        asr_text,
+        ocr_text,
+        response,
+    });
+
+    // Show overlay if requested
+    if args.overlay && updates.is_none() {
+        show_answer(session.overlay.as_ref(), last)?;
+    }
+
+    Ok(())
+}
+
+/// Shows an answer in the listener's overlay, or in a new one that takes over
+/// the process until it is closed.
+fn show_answer(overlay: Option<&Overlay>, answer: &LastAnswer) -> Result<()> {
+    match overlay {
+        Some(overlay) => {
+            overlay.show(&answer.asr_text, &answer.response);
+            Ok(())
+        }
+        None => show_overlay(&answer.asr_text, &answer.ocr_text, &answer.response),
+    }
+}
+
+fn prompt_context<'a>(
+    asr_text: &'a str,
+    ocr_text: &'a str,
+    window: Option<&'a ActiveWindow>,
+    language: &'a str,
+    screenshot: bool,
+) -> PromptContext<'a> {
+    let (title, app_name) = window.map_or(("", ""), |w| (w.title.as_str(), w.app_name.as_str()));
+    PromptContext::new(asr_text, ocr_text, title, app_name, language, screenshot)
+}
+
+fn screen_context<'a>(ocr_text: &'a str, upload: Option<&'a EncodedImage>) -> ScreenContext<'a> {
+    ScreenContext {
+        ocr_text,
+        screenshot: upload,
+    }
+}
+
+/// Encodes the screenshot for the AI backend, or `None` with `--text-only`.
+fn encode_upload(args: &Args, screenshot: &RgbaImage) -> Result<Option<EncodedImage>> {
+    if args.text_only {
+        return Ok(None);
+    }
+    let encoding = ImageEncoding {
+        format: args.image_format,
+        max_dimension: args.image_max_dim,
+        quality: args.image_quality,
+    };
+    encode_screenshot(screenshot, &encoding).map(Some)
+}
+
+/// Masks sensitive data unless `--no-redact` is given.
+fn redactor(args: &Args) -> Result<Redactor> {
+    if args.no_redact {
+        return Ok(Redactor::disabled());
+    }
+    Redactor::new(&args.redact_patterns)
+}
+
+fn describe_redactions(count: usize) -> String {
+    format!("Redacted {} sensitive value{} before upload", count, if count == 1 { "" } else { "s" })
+}
+
+fn describe_upload(image: &EncodedImage) -> String {
+    format!(
+        "Uploading {}x{} {} ({:.0} KB)",
+        image.width,
+        image.height,
+        image.mime_type,
+        image.bytes as f64 / 1024.0
+    )
+}
+
+fn demo_spinner() -> ProgressBar {
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_style(
+        ProgressStyle::default_spinner()
+            .template("{spinner:.cyan} {msg}")
+            .unwrap()
+    );
+    spinner
+}
+
+/// Steps 3 and 4 of the demo: generate the response (streaming it to the
+/// terminal and `overlay` when `--stream-response` is set) and optionally log
+/// it to Solana.
+fn run_demo_response_steps(
+    args: &Args,
+    backend: Arc<dyn ResponseBackend>,
+    prompt: String,
+    screen: &ScreenContext,
+    conversation: &mut Conversation,
+    cancel: &Arc<AtomicBool>,
+    overlay: Option<&Sender<OverlayUpdate>>,
+) -> Result<String> {
+    let spinner = demo_spinner();
 
     // Step 3: AI Response
     println!("{}", "┌─ Step 3: AI Response Generation".bright_yellow().bold());
-    spinner.set_message("Generating intelligent response with vision...");
-    spinner.enable_steady_tick(Duration::from_millis(100));
 
-    let api_key = get_api_key_from_env_or_arg(args.gemini_key.as_deref())?;
-    let response = generate_response(&asr_text, &screenshot_path, &api_key)
-        .context("Gemini API call failed. Check your API key and internet connection.")?;
+    let response = if args.stream_response {
+        println!();
+        println!("{}", "┌─────────────────────────────────────────────────────────────┐".bright_cyan());
+        println!("{} {}", "│".bright_cyan(), "📝 Summary:".bright_white().bold());
+        print!("{} ", "│".bright_cyan());
+        let overlay = overlay.cloned();
+        let on_token = move |token: &str| {
+            let token_text = token.replace('\n', &format!("\n{} ", "│".bright_cyan()));
+            print!("{}", token_text.bright_white());
+            let _ = std::io::stdout().flush();
+            if let Some(tx) = &overlay {
+                let _ = tx.send(OverlayUpdate::Token(token.to_string()));
+            }
+        };
+        let response = ask_backend(backend, prompt, screen, conversation, cancel, Some(Box::new(on_token)))?;
+        println!();
+        println!("{}", "└─────────────────────────────────────────────────────────────┘".bright_cyan());
+        println!();
+        response
+    } else {
+        spinner.set_message(if screen.screenshot.is_some() {
+            "Generating intelligent response with vision..."
+        } else {
+            "Generating response from screen text..."
+        });
+        spinner.enable_steady_tick(Duration::from_millis(100));
 
-    spinner.finish_and_clear();
-    println!("  {} Response generated", "✓".green().bold());
-    println!();
-    println!("{}", "┌─────────────────────────────────────────────────────────────┐".bright_cyan());
-    println!("{} {}", "│".bright_cyan(), "📝 Summary:".bright_white().bold());
-    println!("{} {}", "│".bright_cyan(), response.bright_white());
-    println!("{}", "└─────────────────────────────────────────────────────────────┘".bright_cyan());
-    println!();
+        let response = ask_backend(backend, prompt, screen, conversation, cancel, None)?;
+
+        spinner.finish_and_clear();
+        println!("  {} Response generated", "✓".green().bold());
+        println!();
+        println!("{}", "┌─────────────────────────────────────────────────────────────┐".bright_cyan());
+        println!("{} {}", "│".bright_cyan(), "📝 Summary:".bright_white().bold());
+        println!("{} {}", "│".bright_cyan(), response.bright_white());
+        println!("{}", "└─────────────────────────────────────────────────────────────┘".bright_cyan());
+        println!();
+        response
+    };
 
     // Step 4: Solana Logging (optional)
     if args.solana_log && !args.no_chain {
@@ -244,42 +941,117 @@ fn run_demo_mode(args: &Args) -> Result<()> {
         println!();
     }
 
-    // Show overlay if requested
-    if args.overlay {
-        show_overlay(&asr_text, &ocr_text, &response)?;
-    }
+    Ok(response)
+}
 
-    Ok(())
+type TokenSink = Box<dyn FnMut(&str) + Send>;
+
+/// Asks the backend on a worker thread, so the cancel hotkey can abandon a
+/// request that is still waiting for the server. An abandoned request runs
+/// to completion in the background, but its tokens and reply are dropped and
+/// the conversation is left as it was.
+fn ask_backend(
+    backend: Arc<dyn ResponseBackend>,
+    prompt: String,
+    screen: &ScreenContext,
+    conversation: &mut Conversation,
+    cancel: &Arc<AtomicBool>,
+    on_token: Option<TokenSink>,
+) -> Result<String> {
+    let ocr_text = screen.ocr_text.to_string();
+    let screenshot = screen.screenshot.cloned();
+    let mut worker_conversation = conversation.clone();
+    let worker_cancel = cancel.clone();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let screen = screen_context(&ocr_text, screenshot.as_ref());
+        let result = match on_token {
+            Some(mut on_token) => {
+                let mut on_token = |token: &str| {
+                    if !worker_cancel.load(Ordering::SeqCst) {
+                        on_token(token);
+                    }
+                };
+                generate_response_stream(backend.as_ref(), prompt, &screen, &mut worker_conversation, &mut on_token)
+            }
+            None => generate_response(backend.as_ref(), prompt, &screen, &mut worker_conversation),
+        }
+        .with_context(|| format!("{} API call failed. Check your API key and connection.", backend.name()));
+        let _ = tx.send(result.map(|response| (response, worker_conversation)));
+    });
+
+    loop {
+        match rx.recv_timeout(Duration::from_millis(50)) {
+            Ok(result) => {
+                let (response, updated) = result?;
+                *conversation = updated;
+                return Ok(response);
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                if cancel.load(Ordering::SeqCst) {
+                    bail!("cancelled");
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => bail!("the response thread stopped unexpectedly"),
+        }
+    }
 }
 
 fn run_combined_mode(args: &Args) -> Result<()> {
     println!("=== Combined Mode: ASR + OCR + Response ===\n");
+    let template = PromptTemplate::load(args.prompt.as_deref(), &args.persona)?;
 
     // Collect ASR
-    println!("Recording {}s of audio...", args.duration_secs);
-    let recorded = record_audio(Duration::from_secs(args.duration_secs))
-        .context("failed to record audio")?;
-    let mono_f32 = downmix_to_mono_f32(&recorded.data, recorded.channels, recorded.sample_format);
-    let target_sr = 16_000u32;
-    let audio_16k = if recorded.sample_rate != target_sr {
-        linear_resample(&mono_f32, recorded.sample_rate, target_sr)
-    } else {
-        mono_f32
-    };
+    println!("{}", recording_message(args));
+    let recorded = capture_audio(args, &AtomicBool::new(false))?;
+    let audio_16k = to_whisper_input(args, &recorded)?;
 
-    let asr_text = transcribe_audio(&args.model, &audio_16k, &args.language)?;
+    let redactor = redactor(args)?;
+    let (asr_text, asr_redactions) = redactor.redact_text(&transcribe_audio(&args.model, &audio_16k, &args.language)?);
     println!("ASR: {}\n", if asr_text.trim().is_empty() { "(no speech)" } else { asr_text.trim() });
 
     // Collect OCR
     println!("Capturing screenshot...");
-    let (ocr_text, screenshot_path) = capture_and_ocr(&args.language, args.tesseract.as_deref())?;
+    let mut capture = capture_and_ocr(&ocr_options(args), &capture_options(args, None))?;
+    let redactions = asr_redactions + redactor.redact_capture(&mut capture);
+    let ScreenCapture { text: ocr_text, image: screenshot, window, .. } = capture;
+    if let Some(path) = &args.save_screenshot {
+        save_screenshot(&screenshot, path)?;
+    }
+    if redactions > 0 {
+        println!("{}", describe_redactions(redactions));
+    }
+    if let Some(window) = &window {
+        println!("Window: {} ({})", window.title, window.app_name);
+    }
     println!("OCR: {}\n", if ocr_text.trim().is_empty() { "(no text)" } else { &ocr_text });
+    let upload = encode_upload(args, &screenshot)?;
+    if let Some(image) = &upload {
+        println!("{}\n", describe_upload(image));
+    }
 
     // Generate response
-    let api_key = get_api_key_from_env_or_arg(args.gemini_key.as_deref())?;
-    let response = generate_response(&asr_text, &screenshot_path, &api_key)
-        .context("Gemini API call failed.")?;
-    println!("=== Response ===\n{}", response);
+    let backend = create_backend(args.backend, &backend_options(args))?;
+    let mut conversation = Conversation::new(args.history, args.history_tokens);
+    let context = prompt_context(&asr_text, &ocr_text, window.as_ref(), &args.language, upload.is_some());
+    let prompt = template.render(&context)?;
+    let screen = screen_context(&ocr_text, upload.as_ref());
+    let response = if args.stream_response {
+        println!("=== Response ===");
+        let mut on_token = |token: &str| {
+            print!("{}", token);
+            let _ = std::io::stdout().flush();
+        };
+        let response = generate_response_stream(backend.as_ref(), prompt, &screen, &mut conversation, &mut on_token)
+            .with_context(|| format!("{} API call failed.", backend.name()))?;
+        println!();
+        response
+    } else {
+        let response = generate_response(backend.as_ref(), prompt, &screen, &mut conversation)
+            .with_context(|| format!("{} API call failed.", backend.name()))?;
+        println!("=== Response ===\n{}", response);
+        response
+    };
 
     // Log to Solana if requested
     if args.solana_log {
@@ -301,58 +1073,146 @@ fn run_combined_mode(args: &Args) -> Result<()> {
 }
 
 fn run_hotkey_listener(args: Args) -> Result<()> {
+    let bindings = hotkey::bindings(&args.bind).map_err(anyhow::Error::msg)?;
+
     println!("{}", "\n╔═══════════════════════════════════════════════════════════════╗".bright_cyan());
     println!("{}", "║          🎧 WHISPR - Hotkey Listener Mode                  ║".bright_cyan().bold());
-    println!("{}", "║  Press Ctrl+Shift+W to trigger capture                     ║".bright_cyan());
-    println!("{}", "║  Press Ctrl+C to exit                                       ║".bright_cyan());
     println!("{}", "╚═══════════════════════════════════════════════════════════════╝".bright_cyan());
+    for binding in &bindings {
+        println!("  {}", binding.to_string().bright_cyan());
+    }
+    println!("  {}", format!("{:<16} exit", "Ctrl+C").bright_cyan());
+    if push_to_talk(&args) {
+        println!("  {}", "Push-to-talk: hold the capture or asr-only hotkey while speaking".bright_cyan());
+    }
     println!();
-    println!("{}", "Listening for hotkey...".bright_green());
 
-    let args = Arc::new(args);
-    let ctrl_pressed = Arc::new(Mutex::new(false));
-    let shift_pressed = Arc::new(Mutex::new(false));
+    // The overlay's event loop owns the main thread for the whole session, so
+    // showing an answer does not end the listener.
+    run_overlay(move |overlay| listen_for_hotkeys(args, bindings, overlay))
+}
+
+fn listen_for_hotkeys(mut args: Args, bindings: Vec<Binding>, overlay: Overlay) -> Result<()> {
+    // Load the model once up front; every hotkey press reuses it.
+    println!("{}", "Loading whisper model...".bright_blue());
+    let mut session = Session::new(&args)?;
+    session.overlay = Some(overlay);
+    session.transcriber.warm_up(&args.language)?;
+    if push_to_talk(&args) {
+        let recorder = PushToTalkRecorder::new(&input_options(&args), session.talking.clone())
+            .context("failed to open the microphone for push-to-talk")?;
+        session.recorder = Some(recorder);
+    }
 
-    let callback = move |event: Event| {
-        match event.event_type {
+    println!("{}", "Listening for hotkey...".bright_green());
+
+    // The rdev listener gets its own thread so hotkeys (cancel in particular)
+    // keep working while a capture runs here.
+    let (tx, rx) = mpsc::channel();
+    let cancel = session.cancel.clone();
+    let talking = session.talking.clone();
+    let cursor = Arc::new(Mutex::new(None));
+    let listener_cursor = cursor.clone();
+    let mut hotkeys = Hotkeys::new(bindings);
+    std::thread::spawn(move || {
+        let callback = move |event: Event| match event.event_type {
             EventType::KeyPress(key) => {
-                let mut ctrl = ctrl_pressed.lock().unwrap();
-                let mut shift = shift_pressed.lock().unwrap();
-
-                match key {
-                    Key::ControlLeft | Key::ControlRight => *ctrl = true,
-                    Key::ShiftLeft | Key::ShiftRight => *shift = true,
-                    Key::KeyW => {
-                        if *ctrl && *shift {
-                            println!("\n{}", "🔥 Hotkey triggered! Running demo...".bright_yellow().bold());
-                            
-                            if let Err(e) = run_demo_mode(&args) {
-                                eprintln!("{} {}", "Error:".red().bold(), e);
-                            }
-                            
-                            println!("\n{}", "Listening for hotkey...".bright_green());
-                        }
+                if let Some(action) = hotkeys.press(key) {
+                    match action {
+                        Action::Cancel => cancel.store(true, Ordering::SeqCst),
+                        Action::Capture | Action::AsrOnly => talking.store(true, Ordering::SeqCst),
+                        _ => {}
                     }
-                    _ => {}
+                    let _ = tx.send(action);
                 }
             }
             EventType::KeyRelease(key) => {
-                let mut ctrl = ctrl_pressed.lock().unwrap();
-                let mut shift = shift_pressed.lock().unwrap();
-
-                match key {
-                    Key::ControlLeft | Key::ControlRight => *ctrl = false,
-                    Key::ShiftLeft | Key::ShiftRight => *shift = false,
-                    _ => {}
+                if let Some(Action::Capture | Action::AsrOnly) = hotkeys.release(key) {
+                    talking.store(false, Ordering::SeqCst);
                 }
             }
+            EventType::MouseMove { x, y } => {
+                *listener_cursor.lock().unwrap() = Some((x as i32, y as i32));
+            }
             _ => {}
+        };
+        if let Err(error) = listen(callback) {
+            eprintln!("{} {:?}", "Hotkey listener error:".red().bold(), error);
         }
-    };
+    });
+
+    for action in rx {
+        session.cursor = *cursor.lock().unwrap();
+        let result = match action {
+            Action::Capture => {
+                println!("\n{}", "🔥 Hotkey triggered! Running demo...".bright_yellow().bold());
+                run_demo_mode(&args, &mut session)
+            }
+            Action::OcrOnly => run_listener_ocr(&args, &session),
+            Action::AsrOnly => run_listener_asr(&args, &mut session),
+            Action::RepeatLast => repeat_last_answer(&args, &session),
+            Action::ToggleOverlay => {
+                args.overlay = !args.overlay;
+                println!("\n{} {}", "🪟 Overlay".bright_yellow().bold(), if args.overlay { "on" } else { "off" });
+                Ok(())
+            }
+            Action::Cancel => {
+                // Only reached once the cancelled capture (if any) has stopped.
+                session.cancel.store(false, Ordering::SeqCst);
+                continue;
+            }
+            Action::ResetConversation => {
+                session.conversation.reset();
+                println!("\n{}", "🧹 Conversation reset".bright_yellow().bold());
+                Ok(())
+            }
+        };
+        session.cancel.store(false, Ordering::SeqCst);
+        if let Err(e) = result {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+        }
+        println!("\n{}", "Listening for hotkey...".bright_green());
+    }
+
+    Ok(())
+}
 
-    if let Err(error) = listen(callback) {
-        eprintln!("{} {:?}", "Hotkey listener error:".red().bold(), error);
+fn run_listener_ocr(args: &Args, session: &Session) -> Result<()> {
+    println!("\n{}", "📷 Capturing screen text...".bright_yellow().bold());
+    let capture = capture_and_ocr(&ocr_options(args), &capture_options(args, session.cursor))?;
+    if capture.text.trim().is_empty() {
+        println!("{}", "(no text detected)".dimmed());
+    } else {
+        println!("{}", capture.text);
     }
+    Ok(())
+}
+
+fn run_listener_asr(args: &Args, session: &mut Session) -> Result<()> {
+    println!("\n{}", recording_message(args).bright_yellow().bold());
+    let recorded = session.record(args)?;
+    session.check_cancelled()?;
+    let text = session.transcriber.transcribe(&to_whisper_input(args, &recorded)?, &args.language)?;
+    if text.trim().is_empty() {
+        println!("{}", "(no speech detected)".dimmed());
+    } else {
+        println!("{}", text.trim().bright_white());
+    }
+    Ok(())
+}
 
+fn repeat_last_answer(args: &Args, session: &Session) -> Result<()> {
+    let Some(last) = &session.last_answer else {
+        println!("\n{}", "No answer yet".dimmed());
+        return Ok(());
+    };
+    println!();
+    println!("{}", "┌─────────────────────────────────────────────────────────────┐".bright_cyan());
+    println!("{} {}", "│".bright_cyan(), "📝 Summary:".bright_white().bold());
+    println!("{} {}", "│".bright_cyan(), last.response.bright_white());
+    println!("{}", "└─────────────────────────────────────────────────────────────┘".bright_cyan());
+    if args.overlay {
+        show_answer(session.overlay.as_ref(), last)?;
+    }
     Ok(())
 }
@@ -1,21 +1,389 @@
-use anyhow::{anyhow, Result};
-use std::path::PathBuf;
+use anyhow::{anyhow, Context, Result};
+use image::{DynamicImage, RgbaImage};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
-pub fn capture_and_ocr(language: &str, tesseract_cli: Option<&str>) -> Result<(String, PathBuf)> {
-    // Capture primary screen
-    let screens = screenshots::Screen::all()?;
-    let screen = screens
-        .get(0)
-        .ok_or_else(|| anyhow!("no screens detected for screenshot"))?;
+use crate::capture::{capture_screen, CaptureOptions};
+use crate::preprocess::{preprocess, Mapping, Preprocess};
+use crate::window::ActiveWindow;
 
-    let imgbuf = screen.capture()?;
+pub struct OcrOptions<'a> {
+    pub language: &'a str,
+    pub tesseract_cli: Option<&'a str>,
+    /// Words Tesseract is less sure about than this (0-100) are dropped.
+    pub min_confidence: f32,
+    pub preprocess: Preprocess,
+}
+
+pub struct ScreenCapture {
+    /// Words above the confidence threshold laid out like on screen, see
+    /// [`OcrResult::layout_text`].
+    pub text: String,
+    /// Every recognised word, including those below the confidence
+    /// threshold, so redaction also covers text Tesseract was unsure about.
+    pub ocr: OcrResult,
+    pub min_confidence: f32,
+    /// The unprocessed screenshot, kept in memory for the AI backend.
+    pub image: RgbaImage,
+    /// The focused window, when only that was captured.
+    pub window: Option<ActiveWindow>,
+}
+
+impl ScreenCapture {
+    /// The words confident enough to show or put in the prompt.
+    pub fn confident_ocr(&self) -> OcrResult {
+        self.ocr.clone().filter_confidence(self.min_confidence)
+    }
+}
+
+/// Pixel rectangle in the captured image.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub left: u32,
+    pub top: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl BoundingBox {
+    fn right(&self) -> u32 {
+        self.left + self.width
+    }
+
+    fn bottom(&self) -> u32 {
+        self.top + self.height
+    }
+
+    /// The smallest box around all four corners after mapping them, so a
+    /// box on a rotated image still covers the same pixels on the screenshot.
+    fn mapped(&self, mapping: &Mapping) -> BoundingBox {
+        let corners = [
+            (self.left, self.top),
+            (self.right(), self.top),
+            (self.left, self.bottom()),
+            (self.right(), self.bottom()),
+        ]
+        .map(|(x, y)| mapping.map_point(x as f32, y as f32));
+        let (mut x1, mut y1, mut x2, mut y2) = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+        for (x, y) in corners {
+            x1 = x1.min(x);
+            y1 = y1.min(y);
+            x2 = x2.max(x);
+            y2 = y2.max(y);
+        }
+        let (left, top) = (x1.floor().max(0.0) as u32, y1.floor().max(0.0) as u32);
+        BoundingBox {
+            left,
+            top,
+            width: (x2.ceil().max(0.0) as u32).saturating_sub(left),
+            height: (y2.ceil().max(0.0) as u32).saturating_sub(top),
+        }
+    }
+
+    fn union(&self, other: &BoundingBox) -> BoundingBox {
+        if self.width == 0 && self.height == 0 {
+            return *other;
+        }
+        let left = self.left.min(other.left);
+        let top = self.top.min(other.top);
+        BoundingBox {
+            left,
+            top,
+            width: self.right().max(other.right()) - left,
+            height: self.bottom().max(other.bottom()) - top,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OcrWord {
+    pub text: String,
+    pub bbox: BoundingBox,
+    /// Tesseract's confidence, 0-100.
+    pub confidence: f32,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct OcrLine {
+    pub bbox: BoundingBox,
+    pub words: Vec<OcrWord>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct OcrBlock {
+    pub bbox: BoundingBox,
+    pub lines: Vec<OcrLine>,
+}
+
+/// Tesseract output with layout and per-word confidence.
+#[derive(Clone, Debug, Default)]
+pub struct OcrResult {
+    pub blocks: Vec<OcrBlock>,
+}
+
+impl OcrResult {
+    /// Parses Tesseract's TSV output (`tesseract <image> stdout tsv`).
+    pub fn from_tsv(tsv: &str) -> Result<Self> {
+        let mut result = OcrResult::default();
+        // (block_num, par_num, line_num) of the line being filled.
+        let mut current: Option<(u32, u32, u32)> = None;
+
+        for (index, row) in tsv.lines().enumerate() {
+            if index == 0 && row.starts_with("level") {
+                continue;
+            }
+            let cols: Vec<&str> = row.splitn(12, '\t').collect();
+            if cols.len() < 12 {
+                continue;
+            }
+            let num = |i: usize| -> Result<u32> {
+                cols[i]
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("invalid number '{}' in TSV row {}", cols[i], index + 1))
+            };
+
+            // Levels 1-4 are page, block, paragraph and line; only words carry text.
+            if num(0)? != 5 {
+                continue;
+            }
+            let text = cols[11].trim();
+            if text.is_empty() {
+                continue;
+            }
+            let word = OcrWord {
+                text: text.to_string(),
+                bbox: BoundingBox {
+                    left: num(6)?,
+                    top: num(7)?,
+                    width: num(8)?,
+                    height: num(9)?,
+                },
+                confidence: cols[10].trim().parse().unwrap_or(-1.0),
+            };
+
+            let key = (num(2)?, num(3)?, num(4)?);
+            if current.map(|(block, _, _)| block) != Some(key.0) {
+                result.blocks.push(OcrBlock::default());
+            }
+            let block = result.blocks.last_mut().expect("block pushed above");
+            if current != Some(key) {
+                block.lines.push(OcrLine::default());
+            }
+            let line = block.lines.last_mut().expect("line pushed above");
+            line.bbox = line.bbox.union(&word.bbox);
+            block.bbox = block.bbox.union(&word.bbox);
+            line.words.push(word);
+            current = Some(key);
+        }
+
+        Ok(result)
+    }
+
+    pub fn words(&self) -> impl Iterator<Item = &OcrWord> {
+        self.blocks
+            .iter()
+            .flat_map(|b| &b.lines)
+            .flat_map(|l| &l.words)
+    }
+
+    /// Average word confidence, or `None` if nothing was recognised.
+    pub fn mean_confidence(&self) -> Option<f32> {
+        let (sum, count) = self
+            .words()
+            .fold((0.0, 0usize), |(sum, count), w| (sum + w.confidence, count + 1));
+        (count > 0).then(|| sum / count as f32)
+    }
+
+    /// Drops words below `min_confidence` and any lines or blocks left empty.
+    pub fn filter_confidence(self, min_confidence: f32) -> Self {
+        let blocks = self
+            .blocks
+            .into_iter()
+            .filter_map(|block| {
+                let lines: Vec<OcrLine> = block
+                    .lines
+                    .into_iter()
+                    .filter_map(|line| {
+                        let words: Vec<OcrWord> = line
+                            .words
+                            .into_iter()
+                            .filter(|w| w.confidence >= min_confidence)
+                            .collect();
+                        let bbox = words.iter().fold(BoundingBox::default(), |b, w| b.union(&w.bbox));
+                        (!words.is_empty()).then_some(OcrLine { bbox, words })
+                    })
+                    .collect();
+                let bbox = lines.iter().fold(BoundingBox::default(), |b, l| b.union(&l.bbox));
+                (!lines.is_empty()).then_some(OcrBlock { bbox, lines })
+            })
+            .collect();
+        Self { blocks }
+    }
+
+    /// Moves every bounding box from the preprocessed image back onto the
+    /// screenshot.
+    pub fn mapped(mut self, mapping: &Mapping) -> Self {
+        for block in &mut self.blocks {
+            block.bbox = block.bbox.mapped(mapping);
+            for line in &mut block.lines {
+                line.bbox = line.bbox.mapped(mapping);
+                for word in &mut line.words {
+                    word.bbox = word.bbox.mapped(mapping);
+                }
+            }
+        }
+        self
+    }
+
+    /// All words on one line separated by single spaces.
+    pub fn plain_text(&self) -> String {
+        self.words()
+            .map(|w| w.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// One output line per recognised line and a blank line between blocks.
+    /// Indentation and wide gaps are converted to spaces using the block's
+    /// average character width, so code and table columns stay aligned.
+    pub fn layout_text(&self) -> String {
+        let mut out = Vec::new();
+        for block in &self.blocks {
+            let char_width = block_char_width(block);
+            let spaces = |px: u32| ((px as f32 / char_width).round() as usize).max(1);
+
+            let mut lines = Vec::with_capacity(block.lines.len());
+            for line in &block.lines {
+                let indent = line.bbox.left.saturating_sub(block.bbox.left);
+                let mut text = if indent as f32 >= char_width {
+                    " ".repeat(spaces(indent))
+                } else {
+                    String::new()
+                };
+                let mut prev_right = None;
+                for word in &line.words {
+                    if let Some(right) = prev_right {
+                        let gap = word.bbox.left.saturating_sub(right);
+                        // Normal word spacing is well under two characters.
+                        let count = if gap as f32 > 2.0 * char_width { spaces(gap) } else { 1 };
+                        text.push_str(&" ".repeat(count));
+                    }
+                    text.push_str(&word.text);
+                    prev_right = Some(word.bbox.right());
+                }
+                lines.push(text);
+            }
+            out.push(lines.join("\n"));
+        }
+        out.join("\n\n")
+    }
+}
+
+fn block_char_width(block: &OcrBlock) -> f32 {
+    let (px, chars) = block
+        .lines
+        .iter()
+        .flat_map(|l| &l.words)
+        .fold((0u32, 0usize), |(px, chars), w| (px + w.bbox.width, chars + w.text.chars().count()));
+    if chars == 0 {
+        return 1.0;
+    }
+    (px as f32 / chars as f32).max(1.0)
+}
+
+/// Writes a screenshot to `path`; the format follows the extension.
+pub fn save_screenshot(image: &RgbaImage, path: &Path) -> Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    image
+        .save(path)
+        .with_context(|| format!("failed to save screenshot to {}", path.display()))
+}
+
+pub fn capture_and_ocr(options: &OcrOptions, capture: &CaptureOptions) -> Result<ScreenCapture> {
+    let screenshot = capture_screen(capture)?;
+
+    let (ocr_input, mapping) = preprocess(&screenshot.image, &options.preprocess);
+    let tsv = recognize(&ocr_input, options.language, options.tesseract_cli)?;
+    let ocr = OcrResult::from_tsv(&tsv)?.mapped(&mapping);
+    Ok(ScreenCapture {
+        text: ocr.clone().filter_confidence(options.min_confidence).layout_text(),
+        ocr,
+        min_confidence: options.min_confidence,
+        image: screenshot.image,
+        window: screenshot.window,
+    })
+}
+
+/// Runs OCR and returns Tesseract's TSV output. Uses the linked
+/// libtesseract when built with `native-ocr`,
+/// falling back to the CLI if the library cannot be initialised (e.g. no
+/// traineddata for `language`).
+#[cfg(feature = "native-ocr")]
+fn recognize(image: &DynamicImage, language: &str, tesseract_cli: Option<&str>) -> Result<String> {
+    match native::recognize(image, language) {
+        Ok(text) => Ok(text),
+        Err(e) => {
+            eprintln!("Native Tesseract failed ({e}), falling back to the tesseract CLI");
+            recognize_cli(image, language, tesseract_cli)
+        }
+    }
+}
+
+#[cfg(not(feature = "native-ocr"))]
+fn recognize(image: &DynamicImage, language: &str, tesseract_cli: Option<&str>) -> Result<String> {
+    recognize_cli(image, language, tesseract_cli)
+}
 
-    let out_dir = PathBuf::from("out");
-    std::fs::create_dir_all(&out_dir)?;
-    let img_path = out_dir.join("screenshot.png");
+#[cfg(feature = "native-ocr")]
+mod native {
+    use anyhow::Result;
+    use image::DynamicImage;
+    use std::cell::RefCell;
+    use tesseract::Tesseract;
 
-    image::DynamicImage::ImageRgba8(imgbuf).save(&img_path)?;
+    thread_local! {
+        // Loading traineddata takes longer than recognising a screen, so the
+        // engine is kept for the next capture on this thread.
+        static ENGINE: RefCell<Option<(String, Tesseract)>> = const { RefCell::new(None) };
+    }
+
+    pub fn recognize(image: &DynamicImage, language: &str) -> Result<String> {
+        ENGINE.with(|cell| {
+            let mut cached = cell.borrow_mut();
+            let engine = match cached.take() {
+                Some((lang, engine)) if lang == language => engine,
+                _ => {
+                    let datapath = std::env::var("TESSDATA_PREFIX").ok();
+                    Tesseract::new(datapath.as_deref(), Some(language))?
+                }
+            };
+
+            let (width, height) = (image.width() as i32, image.height() as i32);
+            let bytes_per_pixel = image.color().bytes_per_pixel() as i32;
+            let mut engine = engine
+                .set_frame(image.as_bytes(), width, height, bytes_per_pixel, bytes_per_pixel * width)?
+                .recognize()?;
+            let tsv = engine.get_tsv_text(0)?;
+
+            *cached = Some((language.to_string(), engine));
+            // Unlike the CLI, the library leaves out the header row.
+            Ok(tsv)
+        })
+    }
+}
+
+/// Runs the `tesseract` executable on the (preprocessed) image. The CLI
+/// needs a file, so it goes to a private temp dir that is removed afterwards.
+fn recognize_cli(image: &DynamicImage, language: &str, tesseract_cli: Option<&str>) -> Result<String> {
+    let temp_dir = tempfile::Builder::new()
+        .prefix("whispr-")
+        .tempdir()
+        .context("failed to create temp dir for OCR")?;
+    let img_path = temp_dir.path().join("ocr_input.png");
+    image.save(&img_path)?;
 
     // Prefer Tesseract CLI to avoid native linking issues
     let tess_path = find_tesseract(tesseract_cli)?;
@@ -40,6 +408,7 @@ pub fn capture_and_ocr(language: &str, tesseract_cli: Option<&str>) -> Result<(S
         .arg("stdout")
         .arg("-l")
         .arg(language)
+        .arg("tsv")
         .output()
         .map_err(|e| anyhow!("failed to run tesseract: {e}. Is Tesseract installed and on PATH?"))?;
 
@@ -50,13 +419,7 @@ pub fn capture_and_ocr(language: &str, tesseract_cli: Option<&str>) -> Result<(S
         ));
     }
 
-    let raw = String::from_utf8_lossy(&output.stdout);
-    // Clean: collapse whitespace using simple string operations
-    let collapsed = raw
-        .split_whitespace()
-        .collect::<Vec<_>>()
-        .join(" ");
-    Ok((collapsed, img_path))
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
 }
 
 fn find_tesseract(override_path: Option<&str>) -> Result<PathBuf> {
@@ -101,3 +464,91 @@ fn find_tesseract(override_path: Option<&str>) -> Result<PathBuf> {
     ))
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `tesseract <image> stdout tsv` for two blocks: a code snippet with an
+    /// indented, partly unreadable line and a second paragraph, and a line
+    /// with a wide gap. Rows for levels 1-4 and empty words have conf -1.
+    const TSV: &str = "\
+level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext\n\
+1\t1\t0\t0\t0\t0\t0\t0\t800\t600\t-1\t\n\
+2\t1\t1\t0\t0\t0\t10\t10\t130\t80\t-1\t\n\
+3\t1\t1\t1\t0\t0\t10\t10\t130\t50\t-1\t\n\
+4\t1\t1\t1\t1\t0\t10\t10\t130\t20\t-1\t\n\
+5\t1\t1\t1\t1\t1\t10\t10\t40\t20\t96.5\tfn\n\
+5\t1\t1\t1\t1\t2\t60\t10\t80\t20\t91\tmain()\n\
+4\t1\t1\t1\t2\t0\t50\t40\t70\t20\t-1\t\n\
+5\t1\t1\t1\t2\t1\t50\t40\t40\t20\t88\tlet\n\
+5\t1\t1\t1\t2\t2\t100\t40\t20\t20\t12\tx\n\
+5\t1\t1\t1\t2\t3\t130\t40\t10\t20\t-1\t \n\
+3\t1\t1\t2\t0\t0\t10\t70\t15\t20\t-1\t\n\
+5\t1\t1\t2\t1\t1\t10\t70\t15\t20\t95\t}\n\
+2\t1\t2\t0\t0\t0\t400\t300\t200\t20\t-1\t\n\
+5\t1\t2\t1\t1\t1\t400\t300\t50\t20\t90\tHello\n\
+5\t1\t2\t1\t1\t2\t550\t300\t50\t20\t90\tworld\n";
+
+    fn texts(line: &OcrLine) -> Vec<&str> {
+        line.words.iter().map(|w| w.text.as_str()).collect()
+    }
+
+    #[test]
+    fn groups_words_into_blocks_and_lines() {
+        let ocr = OcrResult::from_tsv(TSV).unwrap();
+        assert_eq!(ocr.blocks.len(), 2);
+
+        let code = &ocr.blocks[0];
+        assert_eq!(code.lines.len(), 3, "a new paragraph starts a new line");
+        assert_eq!(texts(&code.lines[0]), ["fn", "main()"]);
+        assert_eq!(texts(&code.lines[1]), ["let", "x"]);
+        assert_eq!(texts(&code.lines[2]), ["}"]);
+        assert_eq!(texts(&ocr.blocks[1].lines[0]), ["Hello", "world"]);
+
+        assert_eq!(code.lines[0].bbox, BoundingBox { left: 10, top: 10, width: 130, height: 20 });
+        assert_eq!(code.bbox, BoundingBox { left: 10, top: 10, width: 130, height: 80 });
+        assert_eq!(ocr.words().next().unwrap().confidence, 96.5);
+    }
+
+    #[test]
+    fn skips_structure_rows_and_empty_words() {
+        let ocr = OcrResult::from_tsv(TSV).unwrap();
+        assert_eq!(ocr.words().count(), 7);
+        assert!(ocr.words().all(|w| !w.text.is_empty() && w.confidence >= 0.0));
+        assert_eq!(ocr.plain_text(), "fn main() let x } Hello world");
+    }
+
+    #[test]
+    fn rejects_malformed_numbers() {
+        assert!(OcrResult::from_tsv("5\t1\t1\t1\t1\t1\tten\t10\t40\t20\t90\tword").is_err());
+        assert!(OcrResult::from_tsv("").unwrap().blocks.is_empty());
+        assert!(OcrResult::from_tsv("5\t1\t1").unwrap().blocks.is_empty());
+    }
+
+    #[test]
+    fn filters_by_confidence() {
+        let ocr = OcrResult::from_tsv(TSV).unwrap();
+        let mean = ocr.mean_confidence().unwrap();
+        assert!((mean - 562.5 / 7.0).abs() < 1e-3, "{mean}");
+
+        let confident = ocr.filter_confidence(30.0);
+        assert_eq!(texts(&confident.blocks[0].lines[1]), ["let"]);
+        assert_eq!(confident.blocks[0].lines[1].bbox, BoundingBox { left: 50, top: 40, width: 40, height: 20 });
+
+        let none = OcrResult::from_tsv(TSV).unwrap().filter_confidence(99.0);
+        assert!(none.blocks.is_empty());
+        assert_eq!(none.mean_confidence(), None);
+    }
+
+    #[test]
+    fn layout_keeps_indentation_and_columns() {
+        let ocr = OcrResult::from_tsv(TSV).unwrap();
+        // Block 1 averages 15 px per character, so the 40 px indent is 3
+        // spaces; block 2's 100 px gap at 10 px per character is 10.
+        assert_eq!(ocr.layout_text(), "fn main()\n   let x\n}\n\nHello          world");
+        assert_eq!(
+            ocr.filter_confidence(30.0).layout_text(),
+            "fn main()\n   let\n}\n\nHello          world"
+        );
+    }
+}
@@ -0,0 +1,455 @@
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, GrayImage, Luma, RgbaImage};
+use std::str::FromStr;
+
+/// Steps applied to the screenshot before OCR. They always run in the
+/// order grayscale, invert, upscale, deskew, threshold; the image sent to
+/// the AI backend is never modified.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Preprocess {
+    pub grayscale: bool,
+    /// Invert light-on-dark screenshots (dark themes) so text is dark on light.
+    pub invert: bool,
+    /// Scale factor for small fonts; 1.0 leaves the size alone.
+    pub upscale: f32,
+    /// Straighten slightly rotated text. Implies grayscale.
+    pub deskew: bool,
+    /// Binarise with a local mean threshold. Implies grayscale.
+    pub threshold: bool,
+}
+
+const DEFAULT_UPSCALE: f32 = 2.0;
+
+impl Preprocess {
+    const NONE: Preprocess = Preprocess {
+        grayscale: false,
+        invert: false,
+        upscale: 1.0,
+        deskew: false,
+        threshold: false,
+    };
+
+    fn is_none(&self) -> bool {
+        *self == Self::NONE
+    }
+}
+
+impl FromStr for Preprocess {
+    type Err = String;
+
+    /// Comma-separated steps, e.g. `grayscale,invert,upscale=3`, or `none` / `all`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut steps = Self::NONE;
+        for step in s.split(',').map(str::trim).filter(|step| !step.is_empty()) {
+            let (name, value) = match step.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (step, None),
+            };
+            match (name.to_ascii_lowercase().as_str(), value) {
+                ("none", None) => steps = Self::NONE,
+                ("all", None) => {
+                    steps = Preprocess {
+                        grayscale: true,
+                        invert: true,
+                        upscale: DEFAULT_UPSCALE,
+                        deskew: true,
+                        threshold: true,
+                    }
+                }
+                ("grayscale" | "gray", None) => steps.grayscale = true,
+                ("invert", None) => steps.invert = true,
+                ("upscale", None) => steps.upscale = DEFAULT_UPSCALE,
+                ("upscale", Some(factor)) => {
+                    steps.upscale = factor
+                        .parse()
+                        .ok()
+                        .filter(|f: &f32| (1.0..=8.0).contains(f))
+                        .ok_or_else(|| format!("invalid upscale factor '{}': expected 1-8", factor))?;
+                }
+                ("deskew", None) => steps.deskew = true,
+                ("threshold", None) => steps.threshold = true,
+                _ => {
+                    return Err(format!(
+                        "unknown preprocessing step '{}': expected grayscale, invert, upscale[=N], deskew, threshold, all or none",
+                        step
+                    ))
+                }
+            }
+        }
+        Ok(steps)
+    }
+}
+
+/// Maps points on the preprocessed image back onto the screenshot, undoing
+/// the upscale and deskew rotation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mapping {
+    scale: f32,
+    /// Clockwise rotation in degrees applied around `center`.
+    rotation: f32,
+    center: (f32, f32),
+}
+
+impl Mapping {
+    const IDENTITY: Mapping = Mapping {
+        scale: 1.0,
+        rotation: 0.0,
+        center: (0.0, 0.0),
+    };
+
+    pub fn map_point(&self, x: f32, y: f32) -> (f32, f32) {
+        let (x, y) = if self.rotation == 0.0 {
+            (x, y)
+        } else {
+            rotate_point(x, y, self.rotation, self.center)
+        };
+        (x / self.scale, y / self.scale)
+    }
+}
+
+/// Prepares a screenshot for Tesseract. The mapping turns coordinates on
+/// the returned image back into screenshot coordinates.
+pub fn preprocess(image: &RgbaImage, steps: &Preprocess) -> (DynamicImage, Mapping) {
+    if steps.is_none() {
+        return (DynamicImage::ImageRgba8(image.clone()), Mapping::IDENTITY);
+    }
+
+    let (width, height) = image.dimensions();
+    let scaled = (
+        (width as f32 * steps.upscale).round() as u32,
+        (height as f32 * steps.upscale).round() as u32,
+    );
+    let mut mapping = Mapping {
+        scale: steps.upscale,
+        ..Mapping::IDENTITY
+    };
+
+    if !(steps.grayscale || steps.deskew || steps.threshold) {
+        let mut color = DynamicImage::ImageRgba8(image.clone());
+        if steps.invert && is_dark(&imageops::grayscale(image)) {
+            color.invert();
+        }
+        if steps.upscale > 1.0 {
+            color = color.resize_exact(scaled.0, scaled.1, FilterType::CatmullRom);
+        }
+        return (color, mapping);
+    }
+
+    let mut gray = imageops::grayscale(image);
+    if steps.invert && is_dark(&gray) {
+        imageops::invert(&mut gray);
+    }
+    if steps.upscale > 1.0 {
+        gray = imageops::resize(&gray, scaled.0, scaled.1, FilterType::CatmullRom);
+    }
+    if steps.deskew {
+        let angle = detect_skew(&gray);
+        if angle.abs() >= 0.1 {
+            gray = rotate(&gray, angle);
+            mapping.rotation = angle;
+            mapping.center = (gray.width() as f32 / 2.0, gray.height() as f32 / 2.0);
+        }
+    }
+    if steps.threshold {
+        let radius = (16.0 * steps.upscale).round() as u32;
+        gray = adaptive_threshold(&gray, radius, 0.15);
+    }
+    (DynamicImage::ImageLuma8(gray), mapping)
+}
+
+/// True when the mean brightness suggests light text on a dark background.
+fn is_dark(gray: &GrayImage) -> bool {
+    let (sum, count) = gray
+        .pixels()
+        .step_by(4)
+        .fold((0u64, 0u64), |(sum, count), p| (sum + p[0] as u64, count + 1));
+    count > 0 && sum / count < 110
+}
+
+/// Estimates text rotation in degrees (positive when lines rise to the
+/// right) by finding the angle whose row projection of dark pixels is most
+/// sharply peaked.
+fn detect_skew(gray: &GrayImage) -> f32 {
+    // A downscaled copy is plenty to find the angle.
+    let factor = (gray.width() as f32 / 1000.0).max(1.0);
+    let small = imageops::resize(
+        gray,
+        (gray.width() as f32 / factor) as u32,
+        (gray.height() as f32 / factor) as u32,
+        FilterType::Triangle,
+    );
+    let dark: Vec<(f32, f32)> = small
+        .enumerate_pixels()
+        .filter(|(_, _, p)| p[0] < 128)
+        .map(|(x, y, _)| (x as f32, y as f32))
+        .collect();
+    if dark.len() < 100 {
+        return 0.0;
+    }
+
+    let (width, height) = (small.width() as f32, small.height() as f32);
+    let score = |degrees: f32| {
+        let tan = degrees.to_radians().tan();
+        let offset = width * tan.abs();
+        let mut rows = vec![0u32; (height + 2.0 * offset) as usize + 1];
+        for &(x, y) in &dark {
+            rows[(y + x * tan + offset) as usize] += 1;
+        }
+        rows.iter().map(|&n| (n as u64) * (n as u64)).sum::<u64>()
+    };
+
+    (-20..=20)
+        .map(|step| step as f32 * 0.25)
+        .max_by_key(|&degrees| score(degrees))
+        .unwrap_or(0.0)
+}
+
+/// Rotates clockwise by `degrees` around the centre, filling with white.
+fn rotate(gray: &GrayImage, degrees: f32) -> GrayImage {
+    let (width, height) = gray.dimensions();
+    let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+
+    GrayImage::from_fn(width, height, |x, y| {
+        // Map each output pixel back to its source position.
+        let (sx, sy) = rotate_point(x as f32, y as f32, degrees, (cx, cy));
+        sample_bilinear(gray, sx, sy).unwrap_or(Luma([255]))
+    })
+}
+
+/// The position in the unrotated image of point `(x, y)` after rotating
+/// clockwise by `degrees` around `center`.
+fn rotate_point(x: f32, y: f32, degrees: f32, (cx, cy): (f32, f32)) -> (f32, f32) {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let (dx, dy) = (x - cx, y - cy);
+    (cos * dx + sin * dy + cx, -sin * dx + cos * dy + cy)
+}
+
+fn sample_bilinear(gray: &GrayImage, x: f32, y: f32) -> Option<Luma<u8>> {
+    if x < 0.0 || y < 0.0 || x > (gray.width() - 1) as f32 || y > (gray.height() - 1) as f32 {
+        return None;
+    }
+    let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+    let (x1, y1) = ((x0 + 1).min(gray.width() - 1), (y0 + 1).min(gray.height() - 1));
+    let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+    let p = |x, y| gray.get_pixel(x, y)[0] as f32;
+    let top = p(x0, y0) * (1.0 - fx) + p(x1, y0) * fx;
+    let bottom = p(x0, y1) * (1.0 - fx) + p(x1, y1) * fx;
+    Some(Luma([(top * (1.0 - fy) + bottom * fy).round() as u8]))
+}
+
+/// Bradley-Roth thresholding: a pixel becomes black when it is more than
+/// `sensitivity` darker than the mean of its surrounding square. Unlike a
+/// global threshold this copes with gradients and mixed-theme screens.
+fn adaptive_threshold(gray: &GrayImage, radius: u32, sensitivity: f32) -> GrayImage {
+    let (width, height) = gray.dimensions();
+    let row = |y: u32| &gray.as_raw()[(y * width) as usize..][..width as usize];
+
+    // Column sums over the rows of the current window, plus their running
+    // total along the row, give each window sum in O(1) with O(width) memory.
+    // The largest total is width * (2 * radius + 1) * 255, well within u32.
+    let mut columns = vec![0u32; width as usize];
+    let mut prefix = vec![0u32; width as usize + 1];
+    let (mut top, mut bottom) = (0, 0);
+    let mut out = GrayImage::new(width, height);
+
+    for y in 0..height {
+        let y1 = y.saturating_sub(radius);
+        let y2 = (y + radius + 1).min(height);
+        while bottom < y2 {
+            columns.iter_mut().zip(row(bottom)).for_each(|(sum, &p)| *sum += p as u32);
+            bottom += 1;
+        }
+        while top < y1 {
+            columns.iter_mut().zip(row(top)).for_each(|(sum, &p)| *sum -= p as u32);
+            top += 1;
+        }
+        for (x, &sum) in columns.iter().enumerate() {
+            prefix[x + 1] = prefix[x] + sum;
+        }
+
+        for (x, &value) in row(y).iter().enumerate() {
+            let x1 = x.saturating_sub(radius as usize);
+            let x2 = (x + radius as usize + 1).min(width as usize);
+            let count = ((x2 - x1) * (y2 - y1) as usize) as f32;
+            let sum = prefix[x2] - prefix[x1];
+            let black = (value as f32) * count < sum as f32 * (1.0 - sensitivity);
+            out.put_pixel(x as u32, y, Luma([if black { 0 } else { 255 }]));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    /// Centre of the dark pixels in `gray`.
+    fn dark_centroid(gray: &GrayImage) -> (f32, f32) {
+        let dark: Vec<(u32, u32)> = gray
+            .enumerate_pixels()
+            .filter(|(_, _, p)| p[0] < 128)
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        let n = dark.len() as f32;
+        (
+            dark.iter().map(|&(x, _)| x as f32).sum::<f32>() / n,
+            dark.iter().map(|&(_, y)| y as f32).sum::<f32>() / n,
+        )
+    }
+
+    #[test]
+    fn mapping_undoes_rotation_and_upscale() {
+        let mut gray = GrayImage::from_pixel(400, 200, Luma([255]));
+        for y in 58..63 {
+            for x in 318..323 {
+                gray.put_pixel(x, y, Luma([0]));
+            }
+        }
+        let rotated = rotate(&gray, 4.0);
+        let mapping = Mapping {
+            scale: 2.0,
+            rotation: 4.0,
+            center: (200.0, 100.0),
+        };
+
+        let (x, y) = dark_centroid(&rotated);
+        let (mx, my) = mapping.map_point(x, y);
+        // The dot is at (320, 60) on the upscaled image, (160, 30) on the screenshot.
+        assert!((mx - 160.0).abs() < 1.0 && (my - 30.0).abs() < 1.0, "mapped to ({mx}, {my})");
+        assert!((x - 320.0).abs() > 2.0 || (y - 60.0).abs() > 2.0, "rotation moved the dot");
+    }
+
+    #[test]
+    fn no_steps_keeps_coordinates() {
+        let image = RgbaImage::new(8, 8);
+        let (_, mapping) = preprocess(&image, &Preprocess::NONE);
+        assert_eq!(mapping.map_point(5.0, 7.0), (5.0, 7.0));
+    }
+
+    #[test]
+    fn upscale_is_divided_out() {
+        let image = RgbaImage::new(10, 10);
+        let steps: Preprocess = "upscale=3".parse().unwrap();
+        let (upscaled, mapping) = preprocess(&image, &steps);
+        assert_eq!(upscaled.width(), 30);
+        assert_eq!(mapping.map_point(30.0, 15.0), (10.0, 5.0));
+    }
+
+    /// Dark bars rising to the right at `degrees`, like lines of text.
+    fn skewed_lines(degrees: f32) -> GrayImage {
+        let tan = degrees.to_radians().tan();
+        let mut gray = GrayImage::from_pixel(600, 300, Luma([255]));
+        for line in 0..4 {
+            let baseline = 90.0 + 50.0 * line as f32;
+            for x in 50..550 {
+                let y = (baseline - x as f32 * tan).round() as u32;
+                for dy in 0..3 {
+                    gray.put_pixel(x, y + dy, Luma([0]));
+                }
+            }
+        }
+        gray
+    }
+
+    #[test]
+    fn parses_steps() {
+        let steps: Preprocess = " GRAY, invert ,upscale=3".parse().unwrap();
+        assert_eq!(
+            steps,
+            Preprocess {
+                grayscale: true,
+                invert: true,
+                upscale: 3.0,
+                ..Preprocess::NONE
+            }
+        );
+        assert_eq!("upscale".parse::<Preprocess>().unwrap().upscale, DEFAULT_UPSCALE);
+
+        let all: Preprocess = "all".parse().unwrap();
+        assert!(all.grayscale && all.invert && all.deskew && all.threshold);
+        assert_eq!(all.upscale, DEFAULT_UPSCALE);
+        // Later steps override earlier ones.
+        assert_eq!("all,none".parse::<Preprocess>(), Ok(Preprocess::NONE));
+        assert!("none,deskew".parse::<Preprocess>().unwrap().deskew);
+        assert_eq!("".parse::<Preprocess>(), Ok(Preprocess::NONE));
+    }
+
+    #[test]
+    fn rejects_bad_steps() {
+        for bad in ["upscale=9", "upscale=0.5", "upscale=big", "blur", "invert=1", "none=2"] {
+            assert!(bad.parse::<Preprocess>().is_err(), "{bad} was accepted");
+        }
+    }
+
+    #[test]
+    fn inverts_only_dark_screenshots() {
+        assert!(is_dark(&GrayImage::from_pixel(10, 10, Luma([30]))));
+        assert!(!is_dark(&GrayImage::from_pixel(10, 10, Luma([200]))));
+        assert!(!is_dark(&GrayImage::new(0, 0)));
+
+        let steps: Preprocess = "invert".parse().unwrap();
+        let (dark, _) = preprocess(&RgbaImage::from_pixel(4, 4, Rgba([20, 20, 20, 255])), &steps);
+        assert_eq!(dark.to_rgba8().get_pixel(0, 0), &Rgba([235, 235, 235, 255]));
+        let (light, _) = preprocess(&RgbaImage::from_pixel(4, 4, Rgba([220, 220, 220, 255])), &steps);
+        assert_eq!(light.to_rgba8().get_pixel(0, 0), &Rgba([220, 220, 220, 255]));
+    }
+
+    #[test]
+    fn detects_skew_with_positive_angles_rising_to_the_right() {
+        assert!((detect_skew(&skewed_lines(3.0)) - 3.0).abs() <= 0.25);
+        assert!((detect_skew(&skewed_lines(-2.0)) + 2.0).abs() <= 0.25);
+        assert_eq!(detect_skew(&skewed_lines(0.0)), 0.0);
+        // Too little text to tell.
+        assert_eq!(detect_skew(&GrayImage::from_pixel(100, 100, Luma([255]))), 0.0);
+    }
+
+    #[test]
+    fn deskew_straightens_lines() {
+        let gray = skewed_lines(3.0);
+        let image = DynamicImage::ImageLuma8(gray).to_rgba8();
+        let (straight, mapping) = preprocess(&image, &"deskew".parse().unwrap());
+        assert!((mapping.rotation - 3.0).abs() <= 0.25);
+        assert!(detect_skew(&straight.to_luma8()).abs() <= 0.25);
+    }
+
+    #[test]
+    fn threshold_follows_the_local_background() {
+        // A left-to-right gradient with a darker square on each side.
+        let mut gray = GrayImage::from_fn(200, 60, |x, _| Luma([60 + x as u8]));
+        for (left, shade) in [(20, 20), (160, 160)] {
+            for y in 25..35 {
+                for x in left..left + 10 {
+                    gray.put_pixel(x, y, Luma([shade]));
+                }
+            }
+        }
+        let binary = adaptive_threshold(&gray, 16, 0.15);
+
+        assert_eq!(binary.get_pixel(25, 30)[0], 0);
+        assert_eq!(binary.get_pixel(165, 30)[0], 0);
+        // The left square is darker than the background on the right, but
+        // only its own surroundings count.
+        assert_eq!(binary.get_pixel(100, 10)[0], 255);
+        assert_eq!(binary.get_pixel(190, 50)[0], 255);
+    }
+
+    #[test]
+    fn threshold_matches_the_window_mean() {
+        let gray = GrayImage::from_fn(37, 23, |x, y| Luma([((x * 31 + y * 17) % 251) as u8]));
+        let (radius, sensitivity) = (4, 0.15);
+        let binary = adaptive_threshold(&gray, radius, sensitivity);
+
+        for (x, y, pixel) in binary.enumerate_pixels() {
+            let xs = x.saturating_sub(radius)..(x + radius + 1).min(37);
+            let ys = y.saturating_sub(radius)..(y + radius + 1).min(23);
+            let count = (xs.len() * ys.len()) as f32;
+            let gray = &gray;
+            let sum: u32 = ys
+                .flat_map(|y| xs.clone().map(move |x| gray.get_pixel(x, y)[0] as u32))
+                .sum();
+            let black = gray.get_pixel(x, y)[0] as f32 * count < sum as f32 * (1.0 - sensitivity);
+            assert_eq!(pixel[0], if black { 0 } else { 255 }, "at ({x}, {y})");
+        }
+    }
+}
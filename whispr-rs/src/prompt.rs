@@ -0,0 +1,254 @@
+use anyhow::{anyhow, Context, Result};
+use std::path::Path;
+
+use crate::utils::config_dir;
+
+/// Presets compiled into the binary. A file with the same name in
+/// `<config dir>/whispr/prompts/` takes precedence.
+const BUILTIN_PERSONAS: [(&str, &str); 4] = [
+    ("default", include_str!("../prompts/default.txt")),
+    ("code-reviewer", include_str!("../prompts/code-reviewer.txt")),
+    ("note-taker", include_str!("../prompts/note-taker.txt")),
+    ("translator", include_str!("../prompts/translator.txt")),
+];
+
+/// Values available to templates as `{{name}}`.
+pub struct PromptContext<'a> {
+    pub transcript: &'a str,
+    pub ocr_text: &'a str,
+    pub window_title: &'a str,
+    pub app_name: &'a str,
+    pub language: &'a str,
+    pub timestamp: String,
+    /// Whether the screenshot is attached (not with `--text-only`); for
+    /// `{{#screenshot}}` sections.
+    pub screenshot: bool,
+}
+
+impl<'a> PromptContext<'a> {
+    pub fn new(
+        transcript: &'a str,
+        ocr_text: &'a str,
+        window_title: &'a str,
+        app_name: &'a str,
+        language: &'a str,
+        screenshot: bool,
+    ) -> Self {
+        Self {
+            transcript: transcript.trim(),
+            ocr_text: ocr_text.trim(),
+            window_title,
+            app_name,
+            language,
+            timestamp: chrono::Local::now().format("%Y-%m-%d %H:%M").to_string(),
+            screenshot,
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        match name {
+            "transcript" => Some(self.transcript),
+            "ocr" => Some(self.ocr_text),
+            "window_title" => Some(self.window_title),
+            "app_name" => Some(self.app_name),
+            "language" => Some(self.language),
+            "timestamp" => Some(&self.timestamp),
+            "screenshot" => Some(if self.screenshot { "yes" } else { "" }),
+            _ => None,
+        }
+    }
+}
+
+/// A prompt with `{{placeholders}}`.
+///
+/// `{{#name}}...{{/name}}` is only rendered when `name` is non-empty and
+/// `{{^name}}...{{/name}}` only when it is empty, so one template can cover
+/// both "the user said something" and "the user was silent".
+pub struct PromptTemplate {
+    name: String,
+    source: String,
+}
+
+impl PromptTemplate {
+    /// Loads `prompt_file` if given, otherwise the named persona.
+    pub fn load(prompt_file: Option<&Path>, persona: &str) -> Result<Self> {
+        if let Some(path) = prompt_file {
+            let source = std::fs::read_to_string(path)
+                .with_context(|| format!("failed to read prompt template {}", path.display()))?;
+            return Ok(Self {
+                name: path.display().to_string(),
+                source,
+            });
+        }
+
+        if let Some(path) = config_dir().map(|dir| dir.join("prompts").join(format!("{persona}.txt"))) {
+            if path.exists() {
+                let source = std::fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read prompt template {}", path.display()))?;
+                return Ok(Self {
+                    name: persona.to_string(),
+                    source,
+                });
+            }
+        }
+
+        BUILTIN_PERSONAS
+            .iter()
+            .find(|(name, _)| *name == persona)
+            .map(|(name, source)| Self {
+                name: name.to_string(),
+                source: source.to_string(),
+            })
+            .ok_or_else(|| {
+                let builtin: Vec<&str> = BUILTIN_PERSONAS.iter().map(|(name, _)| *name).collect();
+                anyhow!(
+                    "unknown persona '{}'. Built-in personas: {}. Custom ones go in {}",
+                    persona,
+                    builtin.join(", "),
+                    config_dir()
+                        .map(|dir| dir.join("prompts").display().to_string())
+                        .unwrap_or_else(|| "<config dir>/whispr/prompts".to_string())
+                )
+            })
+    }
+
+    pub fn render(&self, ctx: &PromptContext) -> Result<String> {
+        let rendered = render_section(&self.source, ctx)
+            .with_context(|| format!("invalid prompt template '{}'", self.name))?;
+        Ok(rendered.trim().to_string())
+    }
+}
+
+fn render_section(template: &str, ctx: &PromptContext) -> Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after_open = &rest[start + 2..];
+        let end = after_open
+            .find("}}")
+            .ok_or_else(|| anyhow!("unclosed '{{{{'"))?;
+        let tag = after_open[..end].trim();
+        rest = &after_open[end + 2..];
+
+        if let Some(name) = tag.strip_prefix('#').or_else(|| tag.strip_prefix('^')) {
+            let close = format!("{{{{/{}}}}}", name);
+            let body_end = rest
+                .find(&close)
+                .ok_or_else(|| anyhow!("section '{}' is never closed", name))?;
+            let body = &rest[..body_end];
+            rest = &rest[body_end + close.len()..];
+
+            let value = ctx
+                .get(name)
+                .ok_or_else(|| anyhow!("unknown placeholder '{}'", name))?;
+            let show = if tag.starts_with('#') { !value.is_empty() } else { value.is_empty() };
+            if show {
+                out.push_str(&render_section(body, ctx)?);
+            }
+        } else {
+            let value = ctx
+                .get(tag)
+                .ok_or_else(|| anyhow!("unknown placeholder '{}'", tag))?;
+            out.push_str(value);
+        }
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context<'a>(transcript: &'a str, window_title: &'a str) -> PromptContext<'a> {
+        PromptContext {
+            transcript,
+            ocr_text: "fn main() {}",
+            window_title,
+            app_name: "code",
+            language: "en",
+            timestamp: "2024-05-01 09:30".to_string(),
+            screenshot: true,
+        }
+    }
+
+    fn render(source: &str, ctx: &PromptContext) -> Result<String> {
+        PromptTemplate {
+            name: "test".to_string(),
+            source: source.to_string(),
+        }
+        .render(ctx)
+    }
+
+    #[test]
+    fn substitutes_placeholders() {
+        let ctx = context("hello", "main.rs");
+        assert_eq!(
+            render("{{transcript}} | {{ ocr }} | {{app_name}} {{language}} {{timestamp}}", &ctx).unwrap(),
+            "hello | fn main() {} | code en 2024-05-01 09:30"
+        );
+    }
+
+    #[test]
+    fn sections_follow_emptiness() {
+        let source = "{{#transcript}}said {{transcript}}{{/transcript}}{{^transcript}}silent{{/transcript}}";
+        assert_eq!(render(source, &context("hi", "")).unwrap(), "said hi");
+        assert_eq!(render(source, &context("", "")).unwrap(), "silent");
+    }
+
+    #[test]
+    fn sections_nest() {
+        let source = "{{#window_title}}[{{window_title}}{{#transcript}}: {{transcript}}{{/transcript}}]{{/window_title}}";
+        assert_eq!(render(source, &context("hi", "main.rs")).unwrap(), "[main.rs: hi]");
+        assert_eq!(render(source, &context("", "main.rs")).unwrap(), "[main.rs]");
+        assert_eq!(render(source, &context("hi", "")).unwrap(), "");
+    }
+
+    #[test]
+    fn rejects_bad_templates() {
+        let ctx = context("hi", "");
+        for source in [
+            "{{unknown}}",
+            "{{#unknown}}x{{/unknown}}",
+            "{{#transcript}}never closed",
+            "{{transcript",
+        ] {
+            assert!(render(source, &ctx).is_err(), "{source} should fail");
+        }
+    }
+
+    #[test]
+    fn builtin_personas_render() {
+        for (name, source) in BUILTIN_PERSONAS {
+            for ctx in [context("what is this?", "main.rs"), context("", "")] {
+                let text = render(source, &ctx).unwrap_or_else(|e| panic!("{name}: {e:#}"));
+                assert!(!text.contains("{{"), "{name} left a tag: {text}");
+            }
+        }
+    }
+
+    #[test]
+    fn image_wording_depends_on_the_screenshot() {
+        for (name, source) in BUILTIN_PERSONAS {
+            let text_only = PromptContext {
+                screenshot: false,
+                ..context("what is this?", "main.rs")
+            };
+            let text = render(source, &text_only).unwrap();
+            assert!(!text.contains("image"), "{name} mentions an image without one: {text}");
+        }
+        let (_, source) = BUILTIN_PERSONAS[0];
+        assert!(render(source, &context("what is this?", "")).unwrap().contains("in the image"));
+    }
+
+    #[test]
+    fn default_persona_mentions_the_window() {
+        let (_, source) = BUILTIN_PERSONAS[0];
+        let text = render(source, &context("", "Inbox")).unwrap();
+        assert!(text.contains("\"Inbox\" in code"), "{text}");
+        assert!(!render(source, &context("", "")).unwrap().contains("They are looking at"));
+    }
+}
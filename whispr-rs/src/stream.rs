@@ -0,0 +1,175 @@
+use anyhow::{anyhow, Result};
+use cpal::traits::StreamTrait;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::Duration;
+use crate::audio::{
+    build_f32_input_stream, downmix_to_mono, open_input, InputOptions, Transcriber, WHISPER_SAMPLE_RATE,
+};
+use crate::resample::{ResampleQuality, Resampler};
+
+/// Timing of the rolling window fed to whisper while streaming.
+pub struct StreamOptions {
+    /// How much new audio is collected before whisper is re-run on the window.
+    pub step: Duration,
+    /// Maximum window length; once reached the current text is finalized.
+    pub window: Duration,
+    /// Audio carried over into the next window so words at the boundary are not cut.
+    /// Words it repeats from the previous `Final` are dropped from the new text.
+    pub keep: Duration,
+    pub resample_quality: ResampleQuality,
+    pub input: InputOptions,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        Self {
+            step: Duration::from_millis(2000),
+            window: Duration::from_millis(10_000),
+            keep: Duration::from_millis(200),
+            resample_quality: ResampleQuality::Balanced,
+            input: InputOptions::default(),
+        }
+    }
+}
+
+pub enum StreamEvent {
+    /// Best guess for the window that is still growing; replaced by the next event.
+    Partial(String),
+    /// Text for a completed window; will not change anymore.
+    Final(String),
+}
+
+/// Transcribes the microphone continuously until `stop` is set.
+///
+/// Captured audio is downmixed in the cpal callback, resampled to 16 kHz every
+/// `step` and appended to a rolling window that is re-transcribed on a single
+/// persistent whisper state, so text shows up while the user is still talking.
+pub fn stream_transcription<F>(
+    transcriber: &mut Transcriber,
+    language: &str,
+    options: &StreamOptions,
+    stop: &AtomicBool,
+    mut on_event: F,
+) -> Result<()>
+where
+    F: FnMut(StreamEvent),
+{
+    let (device, config) = open_input(&options.input)?;
+    let sample_rate = config.sample_rate().0;
+    let channels = config.channels() as usize;
+
+    let (tx, rx) = mpsc::channel::<Vec<f32>>();
+    let stream = build_f32_input_stream(&device, &config, move |data| {
+        // The receiver only goes away once we are shutting down.
+        let _ = tx.send(downmix_to_mono(data, channels));
+    })?;
+    stream.play()?;
+
+    let step_samples = samples_for(options.step, sample_rate);
+    let window_samples = samples_for(options.window, WHISPER_SAMPLE_RATE);
+    let keep_samples = samples_for(options.keep, WHISPER_SAMPLE_RATE);
+
+    // One resampler for the whole stream so chunk boundaries stay seamless.
+    let mut resampler = Resampler::new(sample_rate, WHISPER_SAMPLE_RATE, options.resample_quality);
+    let mut pending: Vec<f32> = Vec::new();
+    let mut window: Vec<f32> = Vec::new();
+    // Text of the last finalized window; the kept tail is transcribed again.
+    let mut last_final = String::new();
+
+    while !stop.load(Ordering::Relaxed) {
+        match rx.recv_timeout(Duration::from_millis(100)) {
+            Ok(chunk) => pending.extend_from_slice(&chunk),
+            Err(mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                return Err(anyhow!("audio stream closed unexpectedly"));
+            }
+        }
+        if pending.len() < step_samples {
+            continue;
+        }
+
+        window.extend(resampler.process(&pending));
+        pending.clear();
+
+        let text = transcriber.transcribe(&window, language)?;
+        let text = strip_overlap(&last_final, text.trim());
+        if window.len() >= window_samples {
+            if !text.is_empty() {
+                on_event(StreamEvent::Final(text.to_string()));
+                last_final = text.to_string();
+            }
+            let tail = window.len().saturating_sub(keep_samples);
+            window.drain(..tail);
+        } else {
+            on_event(StreamEvent::Partial(text.to_string()));
+        }
+    }
+
+    drop(stream);
+
+    // Flush whatever was said since the last finalized window.
+    window.extend(resampler.process(&pending));
+    window.extend(resampler.flush());
+    if window.len() > keep_samples {
+        let text = transcriber.transcribe(&window, language)?;
+        let text = strip_overlap(&last_final, text.trim());
+        if !text.is_empty() {
+            on_event(StreamEvent::Final(text.to_string()));
+        }
+    }
+
+    Ok(())
+}
+
+fn samples_for(duration: Duration, sample_rate: u32) -> usize {
+    (duration.as_secs_f64() * sample_rate as f64) as usize
+}
+
+/// Drops the leading words of `text` that repeat the end of `previous`.
+/// Only a few words are checked: that is all the kept tail can hold.
+fn strip_overlap<'a>(previous: &str, text: &'a str) -> &'a str {
+    const MAX_OVERLAP_WORDS: usize = 4;
+
+    let normalize = |word: &str| -> String {
+        word.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+    };
+    let previous: Vec<String> = previous.split_whitespace().map(normalize).collect();
+    let words: Vec<String> = text.split_whitespace().take(MAX_OVERLAP_WORDS).map(normalize).collect();
+
+    let overlap = (1..=words.len().min(previous.len()))
+        .rev()
+        .find(|&n| previous[previous.len() - n..] == words[..n])
+        .unwrap_or(0);
+
+    let mut rest = text.trim_start();
+    for _ in 0..overlap {
+        rest = rest.split_once(char::is_whitespace).map_or("", |(_, r)| r).trim_start();
+    }
+    rest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_repeated_boundary_words() {
+        assert_eq!(strip_overlap("and then we went home", "went home. After that"), "After that");
+        assert_eq!(strip_overlap("I said Hello,", "hello there"), "there");
+        assert_eq!(strip_overlap("we went", "we went"), "");
+    }
+
+    #[test]
+    fn keeps_text_without_overlap() {
+        assert_eq!(strip_overlap("", "first words"), "first words");
+        assert_eq!(strip_overlap("we went home", "going out"), "going out");
+        assert_eq!(strip_overlap("we went home", "we went out"), "we went out");
+    }
+
+    #[test]
+    fn only_checks_a_few_words() {
+        let previous = "one two three four five";
+        assert_eq!(strip_overlap(previous, "one two three four five six"), "one two three four five six");
+    }
+}
@@ -1,22 +1,194 @@
 use anyhow::Result;
+use std::sync::mpsc::{self, Receiver, Sender};
 use tao::{
-    event_loop::{ControlFlow, EventLoop},
-    window::WindowBuilder,
+    event_loop::{ControlFlow, EventLoop, EventLoopProxy, EventLoopWindowTarget},
+    window::{Window, WindowBuilder},
     event::{Event as TaoEvent, WindowEvent},
     dpi::{LogicalSize, LogicalPosition},
 };
-use wry::webview::WebViewBuilder;
+use wry::webview::{WebView, WebViewBuilder};
+
+/// Pushed into a streaming overlay while the response is being generated.
+pub enum OverlayUpdate {
+    Token(String),
+    Done,
+    Error(String),
+}
+
+/// Placeholder shown until the first token of a streamed response arrives.
+const THINKING_HTML: &str = "<span class='empty' id='typing'>Thinking...</span>";
 
 pub fn show_overlay(asr: &str, _ocr: &str, response: &str) -> Result<()> {
     let event_loop = EventLoop::new();
-    
+    let window = build_window(&event_loop)?;
+
+    let html = overlay_html(asr, &html_escape(response));
+
+    let _webview = WebViewBuilder::new(window)?
+        .with_html(&html)?
+        .build()?;
+
+    event_loop.run(move |event, _, control_flow| {
+        *control_flow = ControlFlow::Wait;
+        handle_close(&event, control_flow);
+    });
+}
+
+/// Opens the overlay right away and fills in the response as tokens arrive on
+/// `updates`, so the user can start reading before generation has finished.
+pub fn show_overlay_streaming(asr: &str, _ocr: &str, updates: Receiver<OverlayUpdate>) -> Result<()> {
+    let event_loop = EventLoop::<OverlayUpdate>::with_user_event();
+    let window = build_window(&event_loop)?;
+
+    let html = overlay_html(asr, THINKING_HTML);
+
+    let webview = WebViewBuilder::new(window)?
+        .with_html(&html)?
+        .build()?;
+
+    // Forward updates into the event loop; the webview can only be touched from its thread.
+    let proxy = event_loop.create_proxy();
+    std::thread::spawn(move || {
+        for update in updates {
+            if proxy.send_event(update).is_err() {
+                break;
+            }
+        }
+    });
+
+    event_loop.run(move |event, _, control_flow| {
+        *control_flow = ControlFlow::Wait;
+
+        if let TaoEvent::UserEvent(update) = &event {
+            apply_update(&webview, update);
+            return;
+        }
+
+        handle_close(&event, control_flow);
+    });
+}
+
+enum OverlayMessage {
+    Show { asr: String, response: String },
+    Start { asr: String },
+    Update(OverlayUpdate),
+    Quit(i32),
+}
+
+/// Handle to the overlay run by [`run_overlay`]; can be used from any thread.
+#[derive(Clone)]
+pub struct Overlay {
+    proxy: EventLoopProxy<OverlayMessage>,
+}
+
+impl Overlay {
+    /// Opens the overlay with a finished response, replacing what it showed before.
+    pub fn show(&self, asr: &str, response: &str) {
+        let _ = self.proxy.send_event(OverlayMessage::Show {
+            asr: asr.to_string(),
+            response: response.to_string(),
+        });
+    }
+
+    /// Opens the overlay for a response that is still being generated; it is
+    /// filled in from the updates sent on the returned channel.
+    pub fn start(&self, asr: &str) -> Sender<OverlayUpdate> {
+        let _ = self.proxy.send_event(OverlayMessage::Start { asr: asr.to_string() });
+        let (tx, rx) = mpsc::channel();
+        let proxy = self.proxy.clone();
+        std::thread::spawn(move || {
+            for update in rx {
+                if proxy.send_event(OverlayMessage::Update(update)).is_err() {
+                    break;
+                }
+            }
+        });
+        tx
+    }
+}
+
+/// Runs an overlay event loop on the calling (main) thread and `work` on a
+/// new one, for the hotkey listener where the overlay opens many times.
+/// Unlike with [`show_overlay`], closing the window keeps the process
+/// running; it exits once `work` returns, with status 1 if that failed.
+pub fn run_overlay<F>(work: F) -> !
+where
+    F: FnOnce(Overlay) -> Result<()> + Send + 'static,
+{
+    let event_loop = EventLoop::<OverlayMessage>::with_user_event();
+    let proxy = event_loop.create_proxy();
+    let overlay = Overlay { proxy: proxy.clone() };
+    std::thread::spawn(move || {
+        let code = match work(overlay) {
+            Ok(()) => 0,
+            Err(e) => {
+                eprintln!("Error: {:?}", e);
+                1
+            }
+        };
+        let _ = proxy.send_event(OverlayMessage::Quit(code));
+    });
+
+    let mut webview: Option<WebView> = None;
+    event_loop.run(move |event, target, control_flow| {
+        *control_flow = ControlFlow::Wait;
+
+        let html = match event {
+            TaoEvent::UserEvent(OverlayMessage::Show { asr, response }) => overlay_html(&asr, &html_escape(&response)),
+            TaoEvent::UserEvent(OverlayMessage::Start { asr }) => overlay_html(&asr, THINKING_HTML),
+            TaoEvent::UserEvent(OverlayMessage::Update(update)) => {
+                // The user may have closed the window before the response finished.
+                if let Some(webview) = &webview {
+                    apply_update(webview, &update);
+                }
+                return;
+            }
+            TaoEvent::UserEvent(OverlayMessage::Quit(code)) => {
+                *control_flow = ControlFlow::ExitWithCode(code);
+                return;
+            }
+            event => {
+                let mut close = ControlFlow::Wait;
+                handle_close(&event, &mut close);
+                if close != ControlFlow::Wait {
+                    webview = None;
+                }
+                return;
+            }
+        };
+
+        // Drop the previous window before opening the new one.
+        webview = None;
+        match open_webview(target, &html) {
+            Ok(opened) => webview = Some(opened),
+            Err(e) => eprintln!("failed to open overlay: {e}"),
+        }
+    });
+}
+
+fn open_webview<T>(target: &EventLoopWindowTarget<T>, html: &str) -> Result<WebView> {
+    Ok(WebViewBuilder::new(build_window(target)?)?.with_html(html)?.build()?)
+}
+
+fn apply_update(webview: &WebView, update: &OverlayUpdate) {
+    let script = match update {
+        OverlayUpdate::Token(token) => format!("appendToken({})", js_string(token)),
+        OverlayUpdate::Done => "finishResponse()".to_string(),
+        OverlayUpdate::Error(message) => format!("showError({})", js_string(message)),
+    };
+    if let Err(e) = webview.evaluate_script(&script) {
+        eprintln!("overlay update failed: {e}");
+    }
+}
+
+fn build_window<T>(event_loop: &EventLoopWindowTarget<T>) -> Result<Window> {
     let window = WindowBuilder::new()
         .with_title("Whispr AI")
         .with_inner_size(LogicalSize::new(500, 400))
         .with_resizable(true)
         .with_always_on_top(true)
         .with_decorations(false)
-        .build(&event_loop)?;
+        .build(event_loop)?;
 
     // Position in center of screen
     if let Some(monitor) = window.current_monitor() {
@@ -28,7 +200,33 @@ pub fn show_overlay(asr: &str, _ocr: &str, response: &str) -> Result<()> {
         ));
     }
 
-    let html = format!(
+    Ok(window)
+}
+
+/// Exits the event loop when the window is closed or Escape is pressed.
+fn handle_close<T>(event: &TaoEvent<T>, control_flow: &mut ControlFlow) {
+    match event {
+        TaoEvent::WindowEvent {
+            event: WindowEvent::CloseRequested,
+            ..
+        } => *control_flow = ControlFlow::Exit,
+        TaoEvent::WindowEvent {
+            event: WindowEvent::KeyboardInput { event, .. },
+            ..
+        } if event.state == tao::event::ElementState::Pressed => {
+            if let tao::keyboard::Key::Character(ch) = &event.logical_key {
+                if *ch == "Escape" || *ch == "\u{001b}" {
+                    *control_flow = ControlFlow::Exit;
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Builds the overlay page; `response_html` must already be escaped.
+fn overlay_html(asr: &str, response_html: &str) -> String {
+    format!(
         r#"<!DOCTYPE html>
 <html>
 <head>
@@ -213,15 +411,42 @@ pub fn show_overlay(asr: &str, _ocr: &str, response: &str) -> Result<()> {
         
         <div class="ai-section">
             <div class="section-label">✨ Whispr Response</div>
-            <div class="bubble ai-bubble">
+            <div class="bubble ai-bubble" id="response">
                 {}
             </div>
         </div>
     </div>
     
     <div class="input-hint">
-        Press <kbd>ESC</kbd> to close • <kbd>Ctrl+Shift+W</kbd> to capture again
+        Press <kbd>ESC</kbd> to close • your capture hotkey to capture again
     </div>
+    <script>
+        function appendToken(token) {{
+            const response = document.getElementById('response');
+            const typing = document.getElementById('typing');
+            if (typing) {{
+                typing.remove();
+            }}
+            token.split('\n').forEach((line, i) => {{
+                if (i > 0) {{
+                    response.appendChild(document.createElement('br'));
+                }}
+                response.appendChild(document.createTextNode(line));
+            }});
+            const chat = document.querySelector('.chat-container');
+            chat.scrollTop = chat.scrollHeight;
+        }}
+        function finishResponse() {{
+            const typing = document.getElementById('typing');
+            if (typing) {{
+                typing.textContent = 'No response';
+            }}
+        }}
+        function showError(message) {{
+            const response = document.getElementById('response');
+            response.textContent = '⚠️ ' + message;
+        }}
+    </script>
 </body>
 </html>"#,
         if asr.trim().is_empty() {
@@ -229,37 +454,13 @@ pub fn show_overlay(asr: &str, _ocr: &str, response: &str) -> Result<()> {
         } else {
             html_escape(asr)
         },
-        html_escape(response)
-    );
-
-    let _webview = WebViewBuilder::new(window)?
-        .with_html(&html)?
-        .build()?;
-
-    event_loop.run(move |event, _, control_flow| {
-        *control_flow = ControlFlow::Wait;
+        response_html
+    )
+}
 
-        match event {
-            TaoEvent::WindowEvent {
-                event: WindowEvent::CloseRequested,
-                ..
-            } => *control_flow = ControlFlow::Exit,
-            TaoEvent::WindowEvent {
-                event: WindowEvent::KeyboardInput { device_id: _, event, is_synthetic: _, .. },
-                ..
-            } => {
-                if event.state == tao::event::ElementState::Pressed {
-                    if let tao::keyboard::Key::Character(ref ch) = event.logical_key {
-                        let ch_str: &str = ch;
-                        if ch_str == "Escape" || ch_str == "\u{001b}" {
-                            *control_flow = ControlFlow::Exit;
-                        }
-                    }
-                }
-            }
-            _ => {}
-        }
-    });
+/// Encodes `s` as a JavaScript string literal.
+fn js_string(s: &str) -> String {
+    serde_json::to_string(s).unwrap_or_else(|_| "\"\"".to_string())
 }
 
 fn html_escape(s: &str) -> String {
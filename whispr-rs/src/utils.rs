@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 pub fn truncate(s: &str, max_len: usize) -> String {
     if s.len() <= max_len {
         s.to_string()
@@ -6,3 +8,19 @@ pub fn truncate(s: &str, max_len: usize) -> String {
     }
 }
 
+/// Whispr's configuration directory: `$XDG_CONFIG_HOME/whispr`, `%APPDATA%\whispr`
+/// on Windows, or `~/.config/whispr`.
+pub fn config_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            if cfg!(windows) {
+                std::env::var_os("APPDATA").map(PathBuf::from)
+            } else {
+                None
+            }
+        })
+        .or_else(|| home::home_dir().map(|home| home.join(".config")))?;
+    Some(base.join("whispr"))
+}
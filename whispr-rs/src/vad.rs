@@ -0,0 +1,252 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Length of the analysis frames the detector works on.
+const FRAME_MS: u32 = 30;
+/// Frames whose zero-crossing rate is above this look like hiss rather than voice.
+const MAX_SPEECH_ZCR: f32 = 0.35;
+/// Speech must be this many times louder than the tracked background noise.
+const NOISE_FLOOR_FACTOR: f32 = 3.0;
+
+pub struct VadOptions {
+    /// Minimum RMS level (0.0 - 1.0) a frame needs to count as speech.
+    pub threshold: f32,
+    /// Recording stops after this much continuous silence.
+    pub silence_timeout: Duration,
+    /// Hard cap on the length of a single recording once speech started.
+    pub max_duration: Duration,
+    /// Give up if nobody starts talking within this time.
+    pub start_timeout: Duration,
+    /// Audio kept from before speech was detected so the first syllable is not clipped.
+    pub pre_roll: Duration,
+}
+
+impl Default for VadOptions {
+    fn default() -> Self {
+        Self {
+            threshold: 0.01,
+            silence_timeout: Duration::from_millis(1000),
+            max_duration: Duration::from_secs(30),
+            start_timeout: Duration::from_secs(10),
+            pre_roll: Duration::from_millis(300),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VadState {
+    WaitingForSpeech,
+    Speaking,
+    /// Speech ended (silence timeout or max length reached).
+    Done,
+    /// No speech was detected before the start timeout.
+    TimedOut,
+}
+
+/// Energy and zero-crossing based speech detector for mono f32 audio.
+///
+/// Feed samples as they arrive with [`VadSegmenter::push`]; the segmenter keeps
+/// only the utterance (plus a short pre-roll) and reports when it is complete.
+pub struct VadSegmenter {
+    threshold: f32,
+    noise_floor: f32,
+    frame_len: usize,
+    silence_frames: usize,
+    max_samples: usize,
+    start_timeout_samples: usize,
+    pre_roll: VecDeque<f32>,
+    pre_roll_len: usize,
+    frame: Vec<f32>,
+    speech: Vec<f32>,
+    samples_seen: usize,
+    trailing_silence: usize,
+    state: VadState,
+}
+
+impl VadSegmenter {
+    pub fn new(sample_rate: u32, options: &VadOptions) -> Self {
+        let frame_len = (sample_rate * FRAME_MS / 1000).max(1) as usize;
+        let samples = |d: Duration| (d.as_secs_f64() * sample_rate as f64) as usize;
+        Self {
+            threshold: options.threshold,
+            noise_floor: 0.0,
+            frame_len,
+            silence_frames: samples(options.silence_timeout).div_ceil(frame_len).max(1),
+            max_samples: samples(options.max_duration),
+            start_timeout_samples: samples(options.start_timeout),
+            pre_roll: VecDeque::new(),
+            pre_roll_len: samples(options.pre_roll),
+            frame: Vec::with_capacity(frame_len),
+            speech: Vec::new(),
+            samples_seen: 0,
+            trailing_silence: 0,
+            state: VadState::WaitingForSpeech,
+        }
+    }
+
+    pub fn state(&self) -> VadState {
+        self.state
+    }
+
+    pub fn push(&mut self, samples: &[f32]) -> VadState {
+        for &sample in samples {
+            if matches!(self.state, VadState::Done | VadState::TimedOut) {
+                break;
+            }
+            self.frame.push(sample);
+            if self.frame.len() == self.frame_len {
+                let frame = std::mem::take(&mut self.frame);
+                self.process_frame(&frame);
+                self.frame = frame;
+                self.frame.clear();
+            }
+        }
+        self.state
+    }
+
+    /// Returns the captured utterance, dropping most of the trailing silence.
+    pub fn into_speech(mut self) -> Vec<f32> {
+        let keep_silence = self.frame_len * 10;
+        let trailing = (self.trailing_silence * self.frame_len).saturating_sub(keep_silence);
+        let len = self.speech.len().saturating_sub(trailing);
+        self.speech.truncate(len);
+        self.speech
+    }
+
+    fn process_frame(&mut self, frame: &[f32]) {
+        self.samples_seen += frame.len();
+        let speech = self.is_speech(frame);
+
+        match self.state {
+            VadState::WaitingForSpeech => {
+                if speech {
+                    self.state = VadState::Speaking;
+                    self.speech.extend(self.pre_roll.drain(..));
+                    self.speech.extend_from_slice(frame);
+                } else if self.samples_seen >= self.start_timeout_samples {
+                    self.state = VadState::TimedOut;
+                } else {
+                    self.pre_roll.extend(frame.iter().copied());
+                    let excess = self.pre_roll.len().saturating_sub(self.pre_roll_len);
+                    self.pre_roll.drain(..excess);
+                }
+            }
+            VadState::Speaking => {
+                self.speech.extend_from_slice(frame);
+                self.trailing_silence = if speech { 0 } else { self.trailing_silence + 1 };
+                if self.trailing_silence >= self.silence_frames || self.speech.len() >= self.max_samples {
+                    self.state = VadState::Done;
+                }
+            }
+            VadState::Done | VadState::TimedOut => {}
+        }
+    }
+
+    fn is_speech(&mut self, frame: &[f32]) -> bool {
+        let rms = (frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32).sqrt();
+        let crossings = frame
+            .windows(2)
+            .filter(|w| (w[0] >= 0.0) != (w[1] >= 0.0))
+            .count();
+        let zcr = crossings as f32 / frame.len() as f32;
+
+        let level = self.threshold.max(self.noise_floor * NOISE_FLOOR_FACTOR);
+        let speech = rms >= level && zcr <= MAX_SPEECH_ZCR;
+        if !speech {
+            // Slowly follow the background level so a noisy room does not count as speech.
+            self.noise_floor = if self.noise_floor == 0.0 {
+                rms
+            } else {
+                self.noise_floor * 0.95 + rms * 0.05
+            };
+        }
+        speech
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+    /// 30 ms at 16 kHz.
+    const FRAME: usize = 480;
+
+    fn options() -> VadOptions {
+        VadOptions {
+            start_timeout: Duration::from_secs(1),
+            ..VadOptions::default()
+        }
+    }
+
+    fn tone(amplitude: f32, seconds: f32) -> Vec<f32> {
+        let len = (seconds * RATE as f32) as usize;
+        (0..len)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * 200.0 * i as f32 / RATE as f32).sin())
+            .collect()
+    }
+
+    fn constant(value: f32, seconds: f32) -> Vec<f32> {
+        vec![value; (seconds * RATE as f32) as usize]
+    }
+
+    #[test]
+    fn silence_times_out() {
+        let mut vad = VadSegmenter::new(RATE, &options());
+        assert_eq!(vad.push(&constant(0.0, 0.9)), VadState::WaitingForSpeech);
+        assert_eq!(vad.push(&constant(0.0, 0.2)), VadState::TimedOut);
+        assert!(vad.into_speech().is_empty());
+    }
+
+    #[test]
+    fn hiss_is_not_speech() {
+        let hiss: Vec<f32> = (0..RATE as usize * 2).map(|i| if i % 2 == 0 { 0.3 } else { -0.3 }).collect();
+        let mut vad = VadSegmenter::new(RATE, &options());
+        assert_eq!(vad.push(&hiss), VadState::TimedOut);
+    }
+
+    #[test]
+    fn keeps_pre_roll_and_trims_trailing_silence() {
+        let mut vad = VadSegmenter::new(RATE, &options());
+        vad.push(&constant(0.001, 0.48));
+        assert_eq!(vad.state(), VadState::WaitingForSpeech);
+
+        // Odd chunk sizes, as the audio callback delivers them.
+        for chunk in tone(0.3, 0.96).chunks(333) {
+            vad.push(chunk);
+        }
+        assert_eq!(vad.state(), VadState::Speaking);
+        assert_eq!(vad.push(&constant(0.0, 0.9)), VadState::Speaking);
+        assert_eq!(vad.push(&constant(0.0, 0.3)), VadState::Done);
+
+        let pre_roll = 4800;
+        let speech = vad.into_speech();
+        // 300 ms pre-roll, the tone, and 10 frames of the trailing silence.
+        assert_eq!(speech.len(), pre_roll + 15_360 + 10 * FRAME);
+        assert!(speech[..pre_roll].iter().all(|&s| s == 0.001));
+        assert!(speech[pre_roll..pre_roll + 15_360].iter().any(|&s| s > 0.29));
+    }
+
+    #[test]
+    fn noise_floor_raises_the_speech_level() {
+        let quiet_tone = tone(0.02, 0.09);
+        let mut fresh = VadSegmenter::new(RATE, &options());
+        assert_eq!(fresh.push(&quiet_tone), VadState::Speaking);
+
+        let mut noisy = VadSegmenter::new(RATE, &options());
+        noisy.push(&tone(0.007, 0.48));
+        assert_eq!(noisy.push(&quiet_tone), VadState::WaitingForSpeech);
+        assert_eq!(noisy.push(&tone(0.1, 0.09)), VadState::Speaking);
+    }
+
+    #[test]
+    fn stops_at_max_duration() {
+        let options = VadOptions {
+            max_duration: Duration::from_secs(1),
+            ..options()
+        };
+        let mut vad = VadSegmenter::new(RATE, &options);
+        assert_eq!(vad.push(&tone(0.3, 2.0)), VadState::Done);
+        assert_eq!(vad.into_speech().len(), 16_000usize.div_ceil(FRAME) * FRAME);
+    }
+}
@@ -0,0 +1,288 @@
+use anyhow::{anyhow, Result};
+
+/// The focused window and where it is on the desktop (logical pixels).
+#[derive(Clone, Debug)]
+pub struct ActiveWindow {
+    pub title: String,
+    pub app_name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Looks up the focused window. On Wayland only sway and Hyprland expose
+/// this; other compositors fall back to X11, which only sees XWayland apps.
+#[cfg(target_os = "linux")]
+pub fn active_window() -> Result<ActiveWindow> {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        if std::env::var_os("SWAYSOCK").is_some() {
+            return sway_active_window();
+        }
+        if std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+            return hyprland_active_window();
+        }
+    }
+    x11_active_window()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn active_window() -> Result<ActiveWindow> {
+    Err(anyhow!("active-window capture is only supported on Linux (X11, sway and Hyprland)"))
+}
+
+#[cfg(target_os = "linux")]
+fn x11_active_window() -> Result<ActiveWindow> {
+    use xcb::x;
+
+    let (conn, screen_num) = xcb::Connection::connect(None)
+        .map_err(|e| anyhow!("failed to connect to the X server: {}", e))?;
+    let root = conn
+        .get_setup()
+        .roots()
+        .nth(screen_num as usize)
+        .ok_or_else(|| anyhow!("X server has no screen {}", screen_num))?
+        .root();
+
+    let atom = |name: &str| -> Result<x::Atom> {
+        let cookie = conn.send_request(&x::InternAtom {
+            only_if_exists: true,
+            name: name.as_bytes(),
+        });
+        Ok(conn.wait_for_reply(cookie)?.atom())
+    };
+    let property = |window: x::Window, property: x::Atom, r#type: x::Atom| -> Result<x::GetPropertyReply> {
+        let cookie = conn.send_request(&x::GetProperty {
+            delete: false,
+            window,
+            property,
+            r#type,
+            long_offset: 0,
+            long_length: 1024,
+        });
+        Ok(conn.wait_for_reply(cookie)?)
+    };
+
+    let active = property(root, atom("_NET_ACTIVE_WINDOW")?, x::ATOM_WINDOW)?;
+    let window = focused_window(active.value::<x::Window>()).ok_or_else(|| anyhow!("no focused window (the window manager does not set _NET_ACTIVE_WINDOW)"))?;
+
+    // _NET_WM_NAME is UTF-8; WM_NAME is the legacy fallback.
+    let utf8_title = property(window, atom("_NET_WM_NAME")?, atom("UTF8_STRING")?)?;
+    let title = if utf8_title.value::<u8>().is_empty() {
+        property(window, x::ATOM_WM_NAME, x::ATOM_STRING)?.value::<u8>().to_vec()
+    } else {
+        utf8_title.value::<u8>().to_vec()
+    };
+
+    let class = property(window, x::ATOM_WM_CLASS, x::ATOM_STRING)?;
+    let geometry = conn.wait_for_reply(conn.send_request(&x::GetGeometry {
+        drawable: x::Drawable::Window(window),
+    }))?;
+    let origin = conn.wait_for_reply(conn.send_request(&x::TranslateCoordinates {
+        src_window: window,
+        dst_window: root,
+        src_x: 0,
+        src_y: 0,
+    }))?;
+
+    Ok(x11_window(
+        &title,
+        class.value(),
+        (origin.dst_x(), origin.dst_y()),
+        (geometry.width(), geometry.height()),
+    ))
+}
+
+/// The window in a `_NET_ACTIVE_WINDOW` property; window managers set it to
+/// `None` (or leave it empty) while nothing has focus.
+#[cfg(target_os = "linux")]
+fn focused_window(value: &[xcb::x::Window]) -> Option<xcb::x::Window> {
+    use xcb::Xid;
+
+    value.first().copied().filter(|w| !w.is_none())
+}
+
+/// Builds the window from its raw X11 properties. `origin` is the top-left
+/// corner already translated to root (desktop) coordinates.
+#[cfg(target_os = "linux")]
+fn x11_window(title: &[u8], wm_class: &[u8], origin: (i16, i16), size: (u16, u16)) -> ActiveWindow {
+    // WM_CLASS holds "instance\0class\0"; the class is the nicer app name.
+    let app_name = wm_class
+        .split(|&b| b == 0)
+        .rfind(|part| !part.is_empty())
+        .map(|part| String::from_utf8_lossy(part).into_owned())
+        .unwrap_or_default();
+
+    ActiveWindow {
+        title: String::from_utf8_lossy(title).into_owned(),
+        app_name,
+        x: origin.0 as i32,
+        y: origin.1 as i32,
+        width: size.0 as u32,
+        height: size.1 as u32,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn sway_active_window() -> Result<ActiveWindow> {
+    sway_window(&run_json("swaymsg", &["-t", "get_tree", "--raw"])?)
+}
+
+/// The focused window in the output of `swaymsg -t get_tree`.
+#[cfg(target_os = "linux")]
+fn sway_window(tree: &serde_json::Value) -> Result<ActiveWindow> {
+    let node = find_focused(tree).ok_or_else(|| anyhow!("sway reports no focused window"))?;
+
+    let rect = &node["rect"];
+    // Native Wayland apps have an app_id, XWayland ones a WM class.
+    let app_name = node["app_id"]
+        .as_str()
+        .or_else(|| node["window_properties"]["class"].as_str())
+        .unwrap_or_default();
+
+    Ok(ActiveWindow {
+        title: node["name"].as_str().unwrap_or_default().to_string(),
+        app_name: app_name.to_string(),
+        x: rect["x"].as_i64().unwrap_or(0) as i32,
+        y: rect["y"].as_i64().unwrap_or(0) as i32,
+        width: rect["width"].as_u64().unwrap_or(0) as u32,
+        height: rect["height"].as_u64().unwrap_or(0) as u32,
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn find_focused(node: &serde_json::Value) -> Option<&serde_json::Value> {
+    if node["focused"].as_bool() == Some(true) {
+        return Some(node);
+    }
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node[*key].as_array())
+        .flatten()
+        .find_map(find_focused)
+}
+
+#[cfg(target_os = "linux")]
+fn hyprland_active_window() -> Result<ActiveWindow> {
+    hyprland_window(&run_json("hyprctl", &["activewindow", "-j"])?)
+}
+
+/// The window described by `hyprctl activewindow -j`.
+#[cfg(target_os = "linux")]
+fn hyprland_window(window: &serde_json::Value) -> Result<ActiveWindow> {
+    if window["address"].is_null() {
+        return Err(anyhow!("Hyprland reports no focused window"));
+    }
+
+    let coord = |key: &str, i: usize| window[key][i].as_i64().unwrap_or(0);
+    Ok(ActiveWindow {
+        title: window["title"].as_str().unwrap_or_default().to_string(),
+        app_name: window["class"].as_str().unwrap_or_default().to_string(),
+        x: coord("at", 0) as i32,
+        y: coord("at", 1) as i32,
+        width: coord("size", 0) as u32,
+        height: coord("size", 1) as u32,
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn run_json(program: &str, args: &[&str]) -> Result<serde_json::Value> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| anyhow!("failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| anyhow!("unexpected output from {}: {}", program, e))
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use xcb::{x, Xid, XidNew};
+
+    fn geometry(window: &ActiveWindow) -> (i32, i32, u32, u32) {
+        (window.x, window.y, window.width, window.height)
+    }
+
+    #[test]
+    fn x11_focus_needs_a_real_window() {
+        let window = x::Window::new(0x0420_0007);
+        assert_eq!(focused_window(&[window]), Some(window));
+        assert_eq!(focused_window(&[x::Window::none()]), None);
+        assert_eq!(focused_window(&[]), None);
+    }
+
+    #[test]
+    fn x11_window_uses_the_class_and_translated_origin() {
+        let window = x11_window("Inbox – Mail".as_bytes(), b"navigator\0Firefox\0", (-8, 1100), (1280, 720));
+        assert_eq!(window.title, "Inbox – Mail");
+        assert_eq!(window.app_name, "Firefox");
+        assert_eq!(geometry(&window), (-8, 1100, 1280, 720));
+
+        let window = x11_window(b"\xffbad", b"", (0, 0), (1, 1));
+        assert_eq!(window.title, "\u{fffd}bad");
+        assert_eq!(window.app_name, "");
+        assert_eq!(x11_window(b"", b"xterm\0", (0, 0), (1, 1)).app_name, "xterm");
+    }
+
+    #[test]
+    fn sway_finds_the_focused_node_anywhere_in_the_tree() {
+        let tree = json!({
+            "focused": false,
+            "nodes": [{
+                "focused": false,
+                "nodes": [{"focused": false, "name": "other", "nodes": []}],
+                "floating_nodes": [{
+                    "focused": true,
+                    "name": "~/src — kitty",
+                    "app_id": "kitty",
+                    "rect": {"x": 1920, "y": 40, "width": 800, "height": 600},
+                }],
+            }],
+        });
+        let window = sway_window(&tree).unwrap();
+        assert_eq!(window.title, "~/src — kitty");
+        assert_eq!(window.app_name, "kitty");
+        assert_eq!(geometry(&window), (1920, 40, 800, 600));
+    }
+
+    #[test]
+    fn sway_xwayland_windows_use_the_class() {
+        let tree = json!({
+            "nodes": [{
+                "focused": true,
+                "name": "GIMP",
+                "app_id": null,
+                "window_properties": {"class": "Gimp"},
+                "rect": {"x": 0, "y": 0, "width": 10, "height": 20},
+            }],
+        });
+        assert_eq!(sway_window(&tree).unwrap().app_name, "Gimp");
+        assert!(sway_window(&json!({"focused": false, "nodes": []})).is_err());
+    }
+
+    #[test]
+    fn hyprland_window_geometry() {
+        let window = hyprland_window(&json!({
+            "address": "0x55d1",
+            "title": "README.md - Code",
+            "class": "code",
+            "at": [-1280, 25],
+            "size": [1280, 695],
+        }))
+        .unwrap();
+        assert_eq!(window.title, "README.md - Code");
+        assert_eq!(window.app_name, "code");
+        assert_eq!(geometry(&window), (-1280, 25, 1280, 695));
+
+        // `hyprctl activewindow -j` prints `{}` when nothing is focused.
+        assert!(hyprland_window(&json!({})).is_err());
+    }
+}